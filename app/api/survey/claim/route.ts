@@ -1,22 +1,25 @@
 import { NextRequest, NextResponse } from 'next/server';
+import { PublicKey, Transaction } from '@solana/web3.js';
+import { getAssociatedTokenAddressSync } from '@solana/spl-token';
+import { connection, getAdminKeypair } from '@/lib/solana/config';
 import {
-  Connection,
-  PublicKey,
-  Transaction,
-  sendAndConfirmTransaction,
-} from '@solana/web3.js';
+  createClaimAttestationInstruction,
+  createClaimRewardInstruction,
+} from '@/lib/solana/instructions';
 import {
-  getAssociatedTokenAddress,
-  createAssociatedTokenAccountInstruction,
-} from '@solana/spl-token';
-import { connection, getAdminKeypair } from '@/lib/solana/config';
-import { createClaimRewardInstruction } from '@/lib/solana/instructions';
-import { deriveSurveyAddress, deriveParticipantAddress } from '@/lib/solana/account-utils';
+  decodeSurveyAccount,
+  deriveParticipantAddress,
+  deriveSurveyAddress,
+} from '@/lib/solana/account-utils';
+import { getSurveyResponse } from '@/lib/solana/survey-responses';
+
+// How long an attestation stays valid, in seconds
+const ATTESTATION_TTL_SECONDS = 300;
 
 export async function POST(req: NextRequest) {
   try {
     const body = await req.json();
-    const { surveyId, participantWallet, tokenMint } = body;
+    const { surveyId, participantWallet, referrer } = body;
 
     if (!surveyId || !participantWallet) {
       return NextResponse.json(
@@ -25,7 +28,7 @@ export async function POST(req: NextRequest) {
       );
     }
 
-    // Get admin keypair (for paying transaction fees)
+    // Get admin keypair, the survey owner and verifier
     const adminKeypair = getAdminKeypair();
     if (!adminKeypair) {
       return NextResponse.json(
@@ -38,54 +41,65 @@ export async function POST(req: NextRequest) {
     const participant = new PublicKey(participantWallet);
     
     // Derive account addresses
-    const [surveyAccount] = await deriveSurveyAddress(surveyId);
-    const [participantAccount] = await deriveParticipantAddress(surveyId, participant);
+    const [surveyAccount] = await deriveSurveyAddress(adminKeypair.publicKey, surveyId);
+    const [participantAccount] = await deriveParticipantAddress(surveyAccount, participant);
     
-    // Get token accounts
-    const tokenMintPubkey = new PublicKey(tokenMint);
-    const participantTokenAccount = await getAssociatedTokenAddress(
-      tokenMintPubkey,
-      participant
-    );
-    const surveyTokenAccount = await getAssociatedTokenAddress(
-      tokenMintPubkey,
-      surveyAccount,
-      true // Allow PDA owner
-    );
-
-    // Create transaction
-    const transaction = new Transaction();
+    // The reward mint and its token program are recorded on the survey
+    const surveyAccountInfo = await connection.getAccountInfo(surveyAccount);
+    if (!surveyAccountInfo) {
+      return NextResponse.json(
+        { error: 'Survey not found' },
+        { status: 404 }
+      );
+    }
+    const survey = decodeSurveyAccount(surveyAccountInfo.data);
 
-    // Add create associated token account instruction if needed
-    const tokenAccountInfo = await connection.getAccountInfo(participantTokenAccount);
-    if (!tokenAccountInfo) {
-      transaction.add(
-        createAssociatedTokenAccountInstruction(
-          adminKeypair.publicKey,
-          participantTokenAccount,
-          participant,
-          tokenMintPubkey
-        )
+    // Only participants with a recorded response are attested, and always
+    // for the response hash stored with it
+    const response = getSurveyResponse(surveyAccount, participant);
+    if (!response) {
+      return NextResponse.json(
+        { error: 'No survey response recorded for this wallet' },
+        { status: 403 }
       );
     }
+    const responseHash = Buffer.from(response.responseHash, 'hex');
+    const expiry = BigInt(Math.floor(Date.now() / 1000) + ATTESTATION_TTL_SECONDS);
+
+    const participantTokenAccount = getAssociatedTokenAddressSync(
+      survey.tokenMint,
+      participant,
+      false,
+      survey.tokenProgram
+    );
 
-    // Add claim reward instruction
-    transaction.add(
-      createClaimRewardInstruction(
+    // The program creates the participant's token account if it is missing.
+    // The admin attests to the claim as verifier with a short-lived ed25519
+    // signature rather than co-signing the transaction.
+    const transaction = new Transaction().add(
+      createClaimAttestationInstruction({
+        verifier: adminKeypair,
+        surveyAccount,
+        participant,
+        responseHash,
+        expiry,
+      }),
+      await createClaimRewardInstruction({
         participant,
         surveyAccount,
-        participantAccount,
-        participantTokenAccount,
-        surveyTokenAccount,
-        surveyId
-      )
+        tokenMint: survey.tokenMint,
+        tokenProgram: survey.tokenProgram,
+        verifier: adminKeypair.publicKey,
+        surveyId,
+        responseHash,
+        referrer: referrer ? new PublicKey(referrer) : undefined,
+        expiry,
+      })
     );
+    transaction.feePayer = participant;
+    transaction.recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
 
-    // NOTE: In production, this transaction should be signed by the participant
-    // Here we're using admin keypair for demo purposes
-    // In a real app, you would return the transaction for the user to sign
-    
-    // For production, return the transaction to be signed by the user's wallet
+    // The participant signs and submits from their wallet
     const serializedTransaction = transaction.serialize({
       requireAllSignatures: false,
       verifySignatures: false,
@@ -95,6 +109,7 @@ export async function POST(req: NextRequest) {
       success: true,
       transaction: serializedTransaction.toString('base64'),
       message: 'Transaction created. Please sign and submit from your wallet.',
+      expiresAt: Number(expiry),
       accounts: {
         surveyAccount: surveyAccount.toBase58(),
         participantAccount: participantAccount.toBase58(),
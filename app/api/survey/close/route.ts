@@ -1,18 +1,16 @@
 import { NextRequest, NextResponse } from 'next/server';
 import {
-  Connection,
-  PublicKey,
   Transaction,
   sendAndConfirmTransaction,
 } from '@solana/web3.js';
 import { connection, getAdminKeypair } from '@/lib/solana/config';
 import { createCloseSurveyInstruction } from '@/lib/solana/instructions';
-import { deriveSurveyAddress } from '@/lib/solana/account-utils';
+import { decodeSurveyAccount, deriveSurveyAddress } from '@/lib/solana/account-utils';
 
 export async function POST(req: NextRequest) {
   try {
     const body = await req.json();
-    const { surveyId } = body;
+    const { surveyId, force = false } = body;
 
     if (!surveyId) {
       return NextResponse.json(
@@ -31,15 +29,26 @@ export async function POST(req: NextRequest) {
     }
 
     // Derive survey account address
-    const [surveyAccount] = await deriveSurveyAddress(surveyId);
+    const [surveyAccount] = await deriveSurveyAddress(adminKeypair.publicKey, surveyId);
+    const surveyAccountInfo = await connection.getAccountInfo(surveyAccount);
+    if (!surveyAccountInfo) {
+      return NextResponse.json(
+        { error: 'Survey not found' },
+        { status: 404 }
+      );
+    }
+    const survey = decodeSurveyAccount(surveyAccountInfo.data);
 
-    // Create close survey instruction
-    const instruction = createCloseSurveyInstruction(
-      adminKeypair.publicKey,
+    // Create close survey instruction, sweeping the token pool back to the admin
+    const instruction = await createCloseSurveyInstruction({
+      owner: adminKeypair.publicKey,
       surveyAccount,
-      adminKeypair.publicKey, // Return funds to admin
-      surveyId
-    );
+      ownerSolAccount: adminKeypair.publicKey, // Return funds to admin
+      tokenMint: survey.tokenMint,
+      tokenProgram: survey.tokenProgram,
+      surveyId,
+      force,
+    });
 
     // Create and send transaction
     const transaction = new Transaction().add(instruction);
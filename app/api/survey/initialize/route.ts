@@ -1,23 +1,25 @@
 import { NextRequest, NextResponse } from 'next/server';
 import {
-  Connection,
-  Keypair,
   PublicKey,
   Transaction,
   sendAndConfirmTransaction,
 } from '@solana/web3.js';
-import {
-  createAssociatedTokenAccountInstruction,
-  getAssociatedTokenAddress,
-} from '@solana/spl-token';
 import { 
   connection, 
   getAdminKeypair,
   DEFAULT_SOL_REWARD,
   DEFAULT_TOKEN_REWARD 
 } from '@/lib/solana/config';
-import { createInitializeSurveyInstruction } from '@/lib/solana/instructions';
-import { deriveSurveyAddress, generateSurveyId } from '@/lib/solana/account-utils';
+import {
+  NO_DEADLINE,
+  createFundSurveyInstruction,
+  createInitializeSurveyInstruction,
+} from '@/lib/solana/instructions';
+import {
+  deriveSurveyAddress,
+  deriveTokenPoolAddress,
+  generateSurveyId,
+} from '@/lib/solana/account-utils';
 
 export async function POST(req: NextRequest) {
   try {
@@ -43,46 +45,52 @@ export async function POST(req: NextRequest) {
     const surveyId = await generateSurveyId(surveyTitle?.toLowerCase() || 'survey');
     
     // Derive survey account PDA
-    const [surveyAccount] = await deriveSurveyAddress(surveyId);
+    const [surveyAccount] = await deriveSurveyAddress(adminKeypair.publicKey, surveyId);
     
-    // Parse token mint
+    // Parse token mint; its owner is SPL Token or Token-2022
     const tokenMintPubkey = new PublicKey(tokenMint);
-    
-    // Get or create associated token account for the survey
-    const tokenPool = await getAssociatedTokenAddress(
-      tokenMintPubkey,
-      surveyAccount,
-      true // Allow PDA owner
-    );
-
-    // Create transaction
-    const transaction = new Transaction();
-
-    // Add create associated token account instruction if needed
-    const tokenAccountInfo = await connection.getAccountInfo(tokenPool);
-    if (!tokenAccountInfo) {
-      transaction.add(
-        createAssociatedTokenAccountInstruction(
-          adminKeypair.publicKey,
-          tokenPool,
-          surveyAccount,
-          tokenMintPubkey
-        )
+    const mintAccountInfo = await connection.getAccountInfo(tokenMintPubkey);
+    if (!mintAccountInfo) {
+      return NextResponse.json(
+        { error: 'Token mint not found' },
+        { status: 400 }
       );
     }
+    const tokenProgram = mintAccountInfo.owner;
+    
+    // The program creates the token pool, owned by the survey's vault authority
+    const tokenPool = await deriveTokenPoolAddress(surveyAccount, tokenMintPubkey, tokenProgram);
 
-    // Add initialize survey instruction
-    transaction.add(
-      createInitializeSurveyInstruction(
-        adminKeypair.publicKey,
+    const solReward = BigInt(Math.floor(solRewardAmount * 1e9));
+    const tokenReward = BigInt(tokenRewardAmount);
+
+    // Initialize the survey and escrow every slot's rewards, since claims are
+    // refused until deposits cover them. The admin verifies claims with
+    // short-lived ed25519 attestations.
+    const transaction = new Transaction().add(
+      await createInitializeSurveyInstruction({
+        owner: adminKeypair.publicKey,
+        surveyAccount,
+        tokenMint: tokenMintPubkey,
+        tokenProgram,
+        surveyId,
+        solRewardAmount: solReward,
+        tokenRewardAmount: tokenReward,
+        maxParticipants,
+        verifier: adminKeypair.publicKey,
+        startsAt: BigInt(Math.floor(Date.now() / 1000)),
+        endsAt: NO_DEADLINE,
+        claimDeadline: NO_DEADLINE,
+      }),
+      await createFundSurveyInstruction({
+        funder: adminKeypair.publicKey,
         surveyAccount,
-        tokenMintPubkey,
-        tokenPool,
+        tokenMint: tokenMintPubkey,
+        tokenProgram,
         surveyId,
-        solRewardAmount,
-        tokenRewardAmount,
-        maxParticipants
-      )
+        solAmount: solReward * BigInt(maxParticipants),
+        tokenAmount: tokenReward * BigInt(maxParticipants),
+      })
     );
 
     // Send transaction
@@ -0,0 +1,87 @@
+import { NextRequest, NextResponse } from 'next/server';
+import { PublicKey } from '@solana/web3.js';
+import bs58 from 'bs58';
+import { connection, getAdminKeypair } from '@/lib/solana/config';
+import {
+  decodeSurveyAccount,
+  deriveSurveyAddress,
+  isClosedSurveyAccount,
+  surveyResponseMessage,
+} from '@/lib/solana/account-utils';
+import {
+  hashSurveyResponse,
+  saveSurveyResponse,
+  verifyWalletSignature,
+} from '@/lib/solana/survey-responses';
+
+export async function POST(req: NextRequest) {
+  try {
+    const body = await req.json();
+    const { surveyId, participantWallet, answers, signature } = body;
+
+    if (!surveyId || !participantWallet || answers === undefined || !signature) {
+      return NextResponse.json(
+        { error: 'Missing required parameters' },
+        { status: 400 }
+      );
+    }
+
+    // Get admin keypair, the survey owner
+    const adminKeypair = getAdminKeypair();
+    if (!adminKeypair) {
+      return NextResponse.json(
+        { error: 'Admin configuration not set' },
+        { status: 500 }
+      );
+    }
+
+    const participant = new PublicKey(participantWallet);
+    const [surveyAccount] = await deriveSurveyAddress(adminKeypair.publicKey, surveyId);
+
+    // Only open surveys take responses
+    const surveyAccountInfo = await connection.getAccountInfo(surveyAccount);
+    if (!surveyAccountInfo || isClosedSurveyAccount(surveyAccountInfo.data)) {
+      return NextResponse.json(
+        { error: 'Survey not found' },
+        { status: 404 }
+      );
+    }
+    const survey = decodeSurveyAccount(surveyAccountInfo.data);
+    const now = BigInt(Math.floor(Date.now() / 1000));
+    if (!survey.isActive || survey.isPaused || now < survey.startsAt || now > survey.endsAt) {
+      return NextResponse.json(
+        { error: 'Survey is not accepting responses' },
+        { status: 400 }
+      );
+    }
+
+    // The wallet signs the response it submits, so responses cannot be filed
+    // on another wallet's behalf
+    const responseHash = hashSurveyResponse(answers);
+    const message = surveyResponseMessage(surveyId, participant, responseHash);
+    if (!verifyWalletSignature(participant, message, bs58.decode(signature))) {
+      return NextResponse.json(
+        { error: 'Invalid response signature' },
+        { status: 401 }
+      );
+    }
+
+    if (!saveSurveyResponse(surveyAccount, participant, answers, responseHash)) {
+      return NextResponse.json(
+        { error: 'Response already submitted' },
+        { status: 409 }
+      );
+    }
+
+    return NextResponse.json({
+      success: true,
+      responseHash: responseHash.toString('hex'),
+    });
+  } catch (error: any) {
+    console.error('Error submitting survey response:', error);
+    return NextResponse.json(
+      { error: error.message || 'Failed to submit survey response' },
+      { status: 500 }
+    );
+  }
+}
@@ -3,6 +3,9 @@
 import { useState, useCallback } from "react";
 import { usePrivy } from "@privy-io/react-auth";
 import { Connection, PublicKey, Transaction } from "@solana/web3.js";
+import { sha256 } from "crypto-hash";
+import bs58 from "bs58";
+import { surveyResponseMessage } from "@/lib/solana/account-utils";
 
 interface SurveyStatus {
   surveyId: string;
@@ -60,6 +63,55 @@ export function useSolanaSurvey() {
     }
   }, []);
 
+  // Record a completed response, signed by the participant's wallet. Claims
+  // are only attested for wallets with a recorded response.
+  const submitResponse = useCallback(async (
+    surveyId: string,
+    answers: unknown,
+    signMessage: (message: Uint8Array) => Promise<Uint8Array>
+  ) => {
+    if (!user?.wallet?.address) {
+      throw new Error("Wallet not connected");
+    }
+
+    setLoading(true);
+    setError(null);
+
+    try {
+      const participant = new PublicKey(user.wallet.address);
+      const responseHash = new Uint8Array(
+        await sha256(JSON.stringify(answers), { outputFormat: "buffer" })
+      );
+      const signature = await signMessage(
+        surveyResponseMessage(surveyId, participant, responseHash)
+      );
+
+      const response = await fetch("/api/survey/respond", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+          surveyId,
+          participantWallet: user.wallet.address,
+          answers,
+          signature: bs58.encode(signature),
+        }),
+      });
+
+      const data = await response.json();
+
+      if (!response.ok) {
+        throw new Error(data.error || "Failed to submit survey response");
+      }
+
+      return data;
+    } catch (err: any) {
+      setError(err.message);
+      throw err;
+    } finally {
+      setLoading(false);
+    }
+  }, [user]);
+
   const claimReward = useCallback(async (surveyId: string, tokenMint: string) => {
     if (!user?.wallet?.address) {
       throw new Error("Wallet not connected");
@@ -156,6 +208,7 @@ export function useSolanaSurvey() {
 
   return {
     initializeSurvey,
+    submitResponse,
     claimReward,
     getSurveyStatus,
     closeSurvey,
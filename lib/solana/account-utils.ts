@@ -1,43 +1,322 @@
 import { PublicKey } from '@solana/web3.js';
+import { getAssociatedTokenAddressSync } from '@solana/spl-token';
 import { sha256 } from 'crypto-hash';
 import { PROGRAM_ID } from './config';
 
 // PDA seed constants
 const SURVEY_SEED = 'survey';
 const PARTICIPANT_SEED = 'participant';
+const VAULT_SEED = 'vault';
+const NFT_MINT_SEED = 'nft_mint';
 
-// Derive PDA for survey account
+// Longest plain survey ID, the maximum PDA seed length
+const MAX_SURVEY_ID_LEN = 32;
+
+// Seed a survey ID contributes to the survey address: the ID itself, or the
+// SHA-256 of a free-form title for surveys created with `hash_survey_id`
+export async function surveyIdSeed(
+  surveyId: string,
+  hashSurveyId: boolean = false
+): Promise<Buffer> {
+  if (!hashSurveyId) {
+    return Buffer.from(surveyId);
+  }
+  return Buffer.from(await sha256(surveyId, { outputFormat: 'buffer' }));
+}
+
+// Derive PDA for survey account, ["survey", owner, survey_id]
 export async function deriveSurveyAddress(
-  surveyId: string
+  owner: PublicKey,
+  surveyId: string,
+  hashSurveyId: boolean = false
 ): Promise<[PublicKey, number]> {
   return PublicKey.findProgramAddress(
     [
       Buffer.from(SURVEY_SEED),
-      Buffer.from(surveyId),
+      owner.toBuffer(),
+      await surveyIdSeed(surveyId, hashSurveyId),
     ],
     PROGRAM_ID
   );
 }
 
-// Derive PDA for participant account
+// Derive PDA for participant account, ["participant", survey, participant]
 export async function deriveParticipantAddress(
-  surveyId: string,
+  surveyAccount: PublicKey,
   participant: PublicKey
 ): Promise<[PublicKey, number]> {
   return PublicKey.findProgramAddress(
     [
       Buffer.from(PARTICIPANT_SEED),
-      Buffer.from(surveyId),
+      surveyAccount.toBuffer(),
       participant.toBuffer(),
     ],
     PROGRAM_ID
   );
 }
 
-// Generate unique survey ID
+// Derive PDA for the vault authority that owns the token pool, ["vault", survey]
+export async function deriveVaultAuthorityAddress(
+  surveyAccount: PublicKey
+): Promise<[PublicKey, number]> {
+  return PublicKey.findProgramAddress(
+    [Buffer.from(VAULT_SEED), surveyAccount.toBuffer()],
+    PROGRAM_ID
+  );
+}
+
+// Derive PDA for a participant's NFT mint, ["nft_mint", survey, participant]
+export async function deriveNftMintAddress(
+  surveyAccount: PublicKey,
+  participant: PublicKey
+): Promise<[PublicKey, number]> {
+  return PublicKey.findProgramAddress(
+    [
+      Buffer.from(NFT_MINT_SEED),
+      surveyAccount.toBuffer(),
+      participant.toBuffer(),
+    ],
+    PROGRAM_ID
+  );
+}
+
+// The survey's token pool, the vault authority's associated token account
+export async function deriveTokenPoolAddress(
+  surveyAccount: PublicKey,
+  tokenMint: PublicKey,
+  tokenProgram: PublicKey
+): Promise<PublicKey> {
+  const [vaultAuthority] = await deriveVaultAuthorityAddress(surveyAccount);
+  return getAssociatedTokenAddressSync(tokenMint, vaultAuthority, true, tokenProgram);
+}
+
+// Generate unique survey ID, a plain ID of ASCII letters, digits, `-` and `_`
 export async function generateSurveyId(prefix: string = 'survey'): Promise<string> {
   const timestamp = Date.now().toString();
   const randomBytes = Math.random().toString(36).substring(2, 15);
   const hash = await sha256(`${prefix}_${timestamp}_${randomBytes}`);
-  return `${prefix}_${hash.substring(0, 16)}`;
-}
\ No newline at end of file
+  const suffix = hash.substring(0, 16);
+  const safePrefix = prefix
+    .replace(/[^A-Za-z0-9_-]+/g, '-')
+    .substring(0, MAX_SURVEY_ID_LEN - suffix.length - 1);
+  return `${safePrefix}_${suffix}`;
+}
+
+// The message a participant's wallet signs to submit a survey response,
+// naming the survey, the wallet and the SHA-256 of the answers
+export function surveyResponseMessage(
+  surveyId: string,
+  participant: PublicKey,
+  responseHash: Uint8Array
+): Buffer {
+  return Buffer.from(
+    'SolBridge survey response\n' +
+      `survey: ${surveyId}\n` +
+      `participant: ${participant.toBase58()}\n` +
+      `response: ${Buffer.from(responseHash).toString('hex')}`
+  );
+}
+
+// Account layouts, read in place from the zero-copy structs in
+// program/src/state.rs. Each layout lists the struct's fields with their
+// sizes in bytes, in declaration order.
+const ACCOUNT_VERSION = 2;
+const SURVEY_DISCRIMINATOR = Buffer.from([192, 125, 54, 163, 114, 53, 139, 224]);
+const PARTICIPANT_DISCRIMINATOR = Buffer.from([239, 31, 144, 66, 245, 178, 84, 109]);
+const CLOSED_SURVEY_DISCRIMINATOR = Buffer.from([87, 174, 226, 12, 200, 84, 135, 140]);
+const MAX_OPERATORS = 5;
+const MAX_URI_LENGTH = 200;
+const MAX_REWARD_TIERS = 4;
+const REWARD_TIER_LEN = 32;
+const REWARD_CURVE_LEN = 16;
+const REFERRAL_BONUS_LEN = 24;
+
+interface Layout {
+  offsets: Record<string, number>;
+  len: number;
+}
+
+function layout(fields: [string, number][]): Layout {
+  const offsets: Record<string, number> = {};
+  let len = 0;
+  for (const [name, size] of fields) {
+    offsets[name] = len;
+    len += size;
+  }
+  return { offsets, len };
+}
+
+const SURVEY_LAYOUT = layout([
+  ['discriminator', 8],
+  ['version', 1],
+  ['bump', 1],
+  ['vault_bump', 1],
+  ['token_decimals', 1],
+  ['is_active', 1],
+  ['is_paused', 1],
+  ['soulbound_nfts', 1],
+  ['survey_id_len', 1],
+  ['survey_id', MAX_SURVEY_ID_LEN],
+  ['creator', 32],
+  ['owner', 32],
+  ['pending_owner', 32],
+  ['verifier', 32],
+  ['token_mint', 32],
+  ['token_program', 32],
+  ['nft_collection', 32],
+  ['nft_tree', 32],
+  ['operators', 32 * MAX_OPERATORS],
+  ['sol_reward_amount', 8],
+  ['token_reward_amount', 8],
+  ['total_sol_deposited', 8],
+  ['total_tokens_deposited', 8],
+  ['total_sol_paid', 8],
+  ['total_tokens_paid', 8],
+  ['created_at', 8],
+  ['starts_at', 8],
+  ['ends_at', 8],
+  ['claim_deadline', 8],
+  ['nft_tree_leaf_count', 8],
+  ['lottery_draw_slot', 8],
+  ['reward_tiers', REWARD_TIER_LEN * MAX_REWARD_TIERS],
+  ['reward_curve', REWARD_CURVE_LEN],
+  ['referral_bonus', REFERRAL_BONUS_LEN],
+  ['max_participants', 4],
+  ['current_participants', 4],
+  ['sol_claims', 4],
+  ['token_claims', 4],
+  ['lottery_entries', 4],
+  ['referrals', 4],
+  ['referral_bonuses_paid', 4],
+  ['operator_count', 1],
+  ['nft_uri_len', 1],
+  ['nft_uri', MAX_URI_LENGTH],
+  ['hashed_survey_id', 1],
+  ['reward_tier_count', 1],
+  ['is_lottery', 1],
+  ['winners_drawn', 1],
+  ['lottery_commitment', 32],
+  ['lottery_seed', 32],
+  ['_padding', 6],
+]);
+
+const PARTICIPANT_LAYOUT = layout([
+  ['discriminator', 8],
+  ['version', 1],
+  ['bump', 1],
+  ['has_claimed_sol', 1],
+  ['has_claimed_token', 1],
+  ['has_received_nft', 1],
+  ['has_nft_leaf', 1],
+  ['reward_tier', 1],
+  ['reward_bps', 2],
+  ['has_lottery_entry', 1],
+  ['lottery_entry', 4],
+  ['referral_bonus_paid', 1],
+  ['referral_bonuses', 4],
+  ['referrals', 4],
+  ['_padding', 1],
+  ['survey', 32],
+  ['participant', 32],
+  ['response_hash', 32],
+  ['referrer', 32],
+  ['nft_leaf_index', 8],
+  ['claimed_at', 8],
+]);
+
+export interface SurveyAccount {
+  isActive: boolean;
+  isPaused: boolean;
+  owner: PublicKey;
+  verifier: PublicKey;
+  tokenMint: PublicKey;
+  tokenProgram: PublicKey;
+  solRewardAmount: bigint;
+  tokenRewardAmount: bigint;
+  totalSolDeposited: bigint;
+  totalTokensDeposited: bigint;
+  totalSolPaid: bigint;
+  totalTokensPaid: bigint;
+  createdAt: bigint;
+  startsAt: bigint;
+  endsAt: bigint;
+  claimDeadline: bigint;
+  maxParticipants: number;
+  currentParticipants: number;
+  hashedSurveyId: boolean;
+}
+
+export interface ParticipantAccount {
+  hasClaimedSol: boolean;
+  hasClaimedToken: boolean;
+  hasReceivedNft: boolean;
+  survey: PublicKey;
+  participant: PublicKey;
+  claimedAt: bigint | null;
+}
+
+// Reads the fields of one account in place
+function fieldReader(data: Buffer, { offsets }: Layout) {
+  return {
+    bool: (name: string) => data[offsets[name]] !== 0,
+    u32: (name: string) => data.readUInt32LE(offsets[name]),
+    u64: (name: string) => data.readBigUInt64LE(offsets[name]),
+    i64: (name: string) => data.readBigInt64LE(offsets[name]),
+    pubkey: (name: string) => new PublicKey(data.subarray(offsets[name], offsets[name] + 32)),
+  };
+}
+
+function checkAccountType(data: Buffer, discriminator: Buffer, { len }: Layout, name: string) {
+  if (data.length !== len || !data.subarray(0, 8).equals(discriminator)) {
+    throw new Error(`Not a ${name} account`);
+  }
+  if (data[8] !== ACCOUNT_VERSION) {
+    throw new Error(`Unsupported ${name} account version ${data[8]}`);
+  }
+}
+
+// Whether a survey address holds the tombstone left behind by CloseSurvey
+export function isClosedSurveyAccount(data: Buffer): boolean {
+  return data.equals(CLOSED_SURVEY_DISCRIMINATOR);
+}
+
+export function decodeSurveyAccount(data: Buffer): SurveyAccount {
+  checkAccountType(data, SURVEY_DISCRIMINATOR, SURVEY_LAYOUT, 'survey');
+  const read = fieldReader(data, SURVEY_LAYOUT);
+  return {
+    isActive: read.bool('is_active'),
+    isPaused: read.bool('is_paused'),
+    owner: read.pubkey('owner'),
+    verifier: read.pubkey('verifier'),
+    tokenMint: read.pubkey('token_mint'),
+    tokenProgram: read.pubkey('token_program'),
+    solRewardAmount: read.u64('sol_reward_amount'),
+    tokenRewardAmount: read.u64('token_reward_amount'),
+    totalSolDeposited: read.u64('total_sol_deposited'),
+    totalTokensDeposited: read.u64('total_tokens_deposited'),
+    totalSolPaid: read.u64('total_sol_paid'),
+    totalTokensPaid: read.u64('total_tokens_paid'),
+    createdAt: read.i64('created_at'),
+    startsAt: read.i64('starts_at'),
+    endsAt: read.i64('ends_at'),
+    claimDeadline: read.i64('claim_deadline'),
+    maxParticipants: read.u32('max_participants'),
+    currentParticipants: read.u32('current_participants'),
+    hashedSurveyId: read.bool('hashed_survey_id'),
+  };
+}
+
+// Claimed participants record a nonzero `claimed_at`
+export function decodeParticipantAccount(data: Buffer): ParticipantAccount {
+  checkAccountType(data, PARTICIPANT_DISCRIMINATOR, PARTICIPANT_LAYOUT, 'participant');
+  const read = fieldReader(data, PARTICIPANT_LAYOUT);
+  const claimedAt = read.i64('claimed_at');
+  return {
+    hasClaimedSol: read.bool('has_claimed_sol'),
+    hasClaimedToken: read.bool('has_claimed_token'),
+    hasReceivedNft: read.bool('has_received_nft'),
+    survey: read.pubkey('survey'),
+    participant: read.pubkey('participant'),
+    claimedAt: claimedAt === BigInt(0) ? null : claimedAt,
+  };
+}
@@ -1,96 +1,56 @@
 // Borsh schema definitions for serialization
+//
+// Instruction data is a one-byte tag (see `SurveyInstruction`) followed by
+// the borsh encoding of the instruction's payload, as read by
+// `SurveyInstruction::unpack` in program/src/instruction.rs.
 
-export const initializeSurveySchema = new Map([
-  [
-    'InitializeSurveySchema',
-    {
-      kind: 'struct',
-      fields: [
-        ['instruction', 'u8'],
-        ['survey_id', 'string'],
-        ['sol_reward_amount', 'u64'],
-        ['token_reward_amount', 'u64'],
-        ['max_participants', 'u32'],
-      ],
-    },
-  ],
-]);
+import { Schema } from 'borsh';
 
-export const claimRewardSchema = new Map([
-  [
-    'ClaimRewardSchema',
-    {
-      kind: 'struct',
-      fields: [
-        ['instruction', 'u8'],
-        ['survey_id', 'string'],
-      ],
-    },
-  ],
-]);
+const bytes32: Schema = { array: { type: 'u8', len: 32 } };
 
-export const distributeNftSchema = new Map([
-  [
-    'DistributeNftSchema',
-    {
-      kind: 'struct',
-      fields: [
-        ['instruction', 'u8'],
-        ['survey_id', 'string'],
-      ],
-    },
-  ],
-]);
+export const initializeSurveySchema: Schema = {
+  struct: {
+    survey_id: 'string',
+    sol_reward_amount: 'u64',
+    token_reward_amount: 'u64',
+    max_participants: 'u32',
+    verifier: bytes32,
+    starts_at: 'i64',
+    ends_at: 'i64',
+    claim_deadline: 'i64',
+    hash_survey_id: 'bool',
+    lottery_commitment: { option: bytes32 },
+  },
+};
 
-export const closeSurveySchema = new Map([
-  [
-    'CloseSurveySchema',
-    {
-      kind: 'struct',
-      fields: [
-        ['instruction', 'u8'],
-        ['survey_id', 'string'],
-      ],
-    },
-  ],
-]);
+export const claimRewardSchema: Schema = {
+  struct: {
+    survey_id: 'string',
+    response_hash: bytes32,
+    expiry: 'i64',
+    reward: 'u8',
+    reward_tier: 'u8',
+    referrer: { option: bytes32 },
+  },
+};
 
-export const surveyAccountSchema = new Map([
-  [
-    'SurveyAccount',
-    {
-      kind: 'struct',
-      fields: [
-        ['is_initialized', 'bool'],
-        ['survey_id', 'string'],
-        ['owner', [32]],
-        ['sol_reward_amount', 'u64'],
-        ['token_reward_amount', 'u64'],
-        ['token_mint', [32]],
-        ['max_participants', 'u32'],
-        ['current_participants', 'u32'],
-        ['created_at', 'i64'],
-        ['is_active', 'bool'],
-        ['nft_collection', { kind: 'option', type: [32] }],
-      ],
-    },
-  ],
-]);
+export const distributeNftSchema: Schema = {
+  struct: {
+    survey_id: 'string',
+  },
+};
 
-export const participantAccountSchema = new Map([
-  [
-    'ParticipantAccount',
-    {
-      kind: 'struct',
-      fields: [
-        ['is_initialized', 'bool'],
-        ['survey_id', 'string'],
-        ['participant', [32]],
-        ['has_claimed_sol', 'bool'],
-        ['has_claimed_token', 'bool'],
-        ['has_received_nft', 'bool'],
-        ['claimed_at', { kind: 'option', type: 'i64' }],
-      ],
-    },
-  ],
-]);
\ No newline at end of file
+export const closeSurveySchema: Schema = {
+  struct: {
+    survey_id: 'string',
+    force: 'bool',
+  },
+};
+
+export const fundSurveySchema: Schema = {
+  struct: {
+    survey_id: 'string',
+    sol_amount: 'u64',
+    token_amount: 'u64',
+  },
+};
@@ -1,189 +1,406 @@
 import {
+  Ed25519Program,
+  Keypair,
   PublicKey,
   TransactionInstruction,
   SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
   SYSVAR_RENT_PUBKEY,
 } from '@solana/web3.js';
-import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
-import { serialize } from 'borsh';
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
+import { Schema, serialize } from 'borsh';
 import { PROGRAM_ID } from './config';
 import {
   initializeSurveySchema,
   claimRewardSchema,
   distributeNftSchema,
   closeSurveySchema,
+  fundSurveySchema,
 } from './borsh-schema';
+import {
+  deriveNftMintAddress,
+  deriveParticipantAddress,
+  deriveTokenPoolAddress,
+  deriveVaultAuthorityAddress,
+} from './account-utils';
+
+export const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
+  'metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s'
+);
 
-// Instruction Types
+// Instruction tags, in the order of the program's `SurveyInstruction` enum
 export enum SurveyInstruction {
   InitializeSurvey = 0,
   ClaimReward = 1,
   DistributeNft = 2,
   CloseSurvey = 3,
+  FundSurvey = 4,
+  PauseSurvey = 5,
+  ResumeSurvey = 6,
+  UpdateSurvey = 7,
+  ProposeOwner = 8,
+  AcceptOwner = 9,
+  SetOperators = 10,
+  CreateCollection = 11,
+  CreateNftTree = 12,
+  DistributeCompressedNft = 13,
+  EnterLottery = 14,
+  DrawWinners = 15,
+  ClaimReferralBonus = 16,
+  StartLotteryDraw = 17,
 }
 
-// Instruction Data Classes
-export class InitializeSurveyData {
-  instruction: number;
-  survey_id: string;
-  sol_reward_amount: bigint;
-  token_reward_amount: bigint;
-  max_participants: number;
-
-  constructor(fields: {
-    survey_id: string;
-    sol_reward_amount: bigint;
-    token_reward_amount: bigint;
-    max_participants: number;
-  }) {
-    this.instruction = SurveyInstruction.InitializeSurvey;
-    this.survey_id = fields.survey_id;
-    this.sol_reward_amount = fields.sol_reward_amount;
-    this.token_reward_amount = fields.token_reward_amount;
-    this.max_participants = fields.max_participants;
-  }
+// Which reward legs a claim pays out
+export enum RewardSelector {
+  Sol = 0,
+  Token = 1,
+  Both = 2,
 }
 
-export class ClaimRewardData {
-  instruction: number;
-  survey_id: string;
+// An i64 timestamp far enough out to never be reached
+export const NO_DEADLINE = BigInt('9223372036854775807');
 
-  constructor(fields: { survey_id: string }) {
-    this.instruction = SurveyInstruction.ClaimReward;
-    this.survey_id = fields.survey_id;
-  }
+// Encode an instruction as its tag followed by the borsh payload
+function encodeInstruction(
+  instruction: SurveyInstruction,
+  schema: Schema,
+  payload: unknown
+): Buffer {
+  return Buffer.concat([Buffer.from([instruction]), Buffer.from(serialize(schema, payload))]);
 }
 
-export class DistributeNftData {
-  instruction: number;
-  survey_id: string;
-
-  constructor(fields: { survey_id: string }) {
-    this.instruction = SurveyInstruction.DistributeNft;
-    this.survey_id = fields.survey_id;
-  }
+function bytes(value: PublicKey | Uint8Array): number[] {
+  return Array.from(value instanceof PublicKey ? value.toBytes() : value);
 }
 
-export class CloseSurveyData {
-  instruction: number;
-  survey_id: string;
+function findMetadataAddress(mint: PublicKey, suffix: Buffer[] = []): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from('metadata'),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+      ...suffix,
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  )[0];
+}
 
-  constructor(fields: { survey_id: string }) {
-    this.instruction = SurveyInstruction.CloseSurvey;
-    this.survey_id = fields.survey_id;
-  }
+function findMasterEditionAddress(mint: PublicKey): PublicKey {
+  return findMetadataAddress(mint, [Buffer.from('edition')]);
 }
 
 // Helper functions to create instructions
-export function createInitializeSurveyInstruction(
-  owner: PublicKey,
-  surveyAccount: PublicKey,
-  tokenMint: PublicKey,
-  tokenPool: PublicKey,
-  surveyId: string,
-  solRewardAmount: number,
-  tokenRewardAmount: number,
-  maxParticipants: number
-): TransactionInstruction {
-  const data = serialize(
-    initializeSurveySchema,
-    new InitializeSurveyData({
-      survey_id: surveyId,
-      sol_reward_amount: BigInt(Math.floor(solRewardAmount * 1e9)),
-      token_reward_amount: BigInt(tokenRewardAmount),
-      max_participants: maxParticipants,
-    })
+export async function createInitializeSurveyInstruction(params: {
+  owner: PublicKey;
+  surveyAccount: PublicKey;
+  tokenMint: PublicKey;
+  tokenProgram: PublicKey;
+  surveyId: string;
+  solRewardAmount: bigint;
+  tokenRewardAmount: bigint;
+  maxParticipants: number;
+  verifier: PublicKey;
+  startsAt: bigint;
+  endsAt: bigint;
+  claimDeadline: bigint;
+  hashSurveyId?: boolean;
+  lotteryCommitment?: Uint8Array;
+}): Promise<TransactionInstruction> {
+  const [vaultAuthority] = await deriveVaultAuthorityAddress(params.surveyAccount);
+  const tokenPool = await deriveTokenPoolAddress(
+    params.surveyAccount,
+    params.tokenMint,
+    params.tokenProgram
   );
+  const data = encodeInstruction(SurveyInstruction.InitializeSurvey, initializeSurveySchema, {
+    survey_id: params.surveyId,
+    sol_reward_amount: params.solRewardAmount,
+    token_reward_amount: params.tokenRewardAmount,
+    max_participants: params.maxParticipants,
+    verifier: bytes(params.verifier),
+    starts_at: params.startsAt,
+    ends_at: params.endsAt,
+    claim_deadline: params.claimDeadline,
+    hash_survey_id: params.hashSurveyId ?? false,
+    lottery_commitment: params.lotteryCommitment ? bytes(params.lotteryCommitment) : null,
+  });
 
   return new TransactionInstruction({
     keys: [
-      { pubkey: owner, isSigner: true, isWritable: false },
-      { pubkey: surveyAccount, isSigner: false, isWritable: true },
-      { pubkey: tokenMint, isSigner: false, isWritable: false },
+      { pubkey: params.owner, isSigner: true, isWritable: true },
+      { pubkey: params.surveyAccount, isSigner: false, isWritable: true },
+      { pubkey: params.tokenMint, isSigner: false, isWritable: false },
       { pubkey: tokenPool, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+      { pubkey: vaultAuthority, isSigner: false, isWritable: false },
+      { pubkey: params.tokenProgram, isSigner: false, isWritable: false },
+      { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
     ],
     programId: PROGRAM_ID,
-    data: Buffer.from(data),
+    data,
   });
 }
 
-export function createClaimRewardInstruction(
-  participant: PublicKey,
-  surveyAccount: PublicKey,
-  participantAccount: PublicKey,
-  participantTokenAccount: PublicKey,
-  surveyTokenAccount: PublicKey,
-  surveyId: string
-): TransactionInstruction {
-  const data = serialize(
-    claimRewardSchema,
-    new ClaimRewardData({ survey_id: surveyId })
+export async function createFundSurveyInstruction(params: {
+  funder: PublicKey;
+  surveyAccount: PublicKey;
+  tokenMint: PublicKey;
+  tokenProgram: PublicKey;
+  surveyId: string;
+  solAmount: bigint;
+  tokenAmount: bigint;
+}): Promise<TransactionInstruction> {
+  const funderTokenAccount = getAssociatedTokenAddressSync(
+    params.tokenMint,
+    params.funder,
+    false,
+    params.tokenProgram
   );
+  const tokenPool = await deriveTokenPoolAddress(
+    params.surveyAccount,
+    params.tokenMint,
+    params.tokenProgram
+  );
+  const data = encodeInstruction(SurveyInstruction.FundSurvey, fundSurveySchema, {
+    survey_id: params.surveyId,
+    sol_amount: params.solAmount,
+    token_amount: params.tokenAmount,
+  });
 
   return new TransactionInstruction({
     keys: [
-      { pubkey: participant, isSigner: true, isWritable: true },
-      { pubkey: surveyAccount, isSigner: false, isWritable: true },
-      { pubkey: participantAccount, isSigner: false, isWritable: true },
-      { pubkey: participantTokenAccount, isSigner: false, isWritable: true },
-      { pubkey: surveyTokenAccount, isSigner: false, isWritable: true },
-      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: params.funder, isSigner: true, isWritable: true },
+      { pubkey: params.surveyAccount, isSigner: false, isWritable: true },
+      { pubkey: funderTokenAccount, isSigner: false, isWritable: true },
+      { pubkey: tokenPool, isSigner: false, isWritable: true },
+      { pubkey: params.tokenProgram, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: params.tokenMint, isSigner: false, isWritable: false },
     ],
     programId: PROGRAM_ID,
-    data: Buffer.from(data),
+    data,
+  });
+}
+
+// The message a survey verifier signs to attest to a claim, as built by
+// `attestation_message` in program/src/attestation.rs
+export function attestationMessage(params: {
+  surveyAccount: PublicKey;
+  participant: PublicKey;
+  responseHash: Uint8Array;
+  expiry: bigint;
+  rewardTier?: number;
+}): Buffer {
+  const expiry = Buffer.alloc(8);
+  expiry.writeBigInt64LE(params.expiry);
+  return Buffer.concat([
+    params.surveyAccount.toBuffer(),
+    params.participant.toBuffer(),
+    Buffer.from(params.responseHash),
+    expiry,
+    Buffer.from([params.rewardTier ?? 0]),
+  ]);
+}
+
+// The ed25519 verification that must immediately precede an attested claim
+export function createClaimAttestationInstruction(params: {
+  verifier: Keypair;
+  surveyAccount: PublicKey;
+  participant: PublicKey;
+  responseHash: Uint8Array;
+  expiry: bigint;
+  rewardTier?: number;
+}): TransactionInstruction {
+  return Ed25519Program.createInstructionWithPrivateKey({
+    privateKey: params.verifier.secretKey,
+    message: attestationMessage(params),
+  });
+}
+
+// The verifier authorizes the claim either by co-signing the transaction or,
+// when `expiry` is given, with an ed25519 attestation placed just before it
+// (see `createClaimAttestationInstruction`)
+export async function createClaimRewardInstruction(params: {
+  participant: PublicKey;
+  surveyAccount: PublicKey;
+  tokenMint: PublicKey;
+  tokenProgram: PublicKey;
+  verifier: PublicKey;
+  surveyId: string;
+  responseHash: Uint8Array;
+  reward?: RewardSelector;
+  rewardTier?: number;
+  referrer?: PublicKey;
+  expiry?: bigint;
+}): Promise<TransactionInstruction> {
+  const [participantAccount] = await deriveParticipantAddress(
+    params.surveyAccount,
+    params.participant
+  );
+  const [vaultAuthority] = await deriveVaultAuthorityAddress(params.surveyAccount);
+  const participantTokenAccount = getAssociatedTokenAddressSync(
+    params.tokenMint,
+    params.participant,
+    false,
+    params.tokenProgram
+  );
+  const tokenPool = await deriveTokenPoolAddress(
+    params.surveyAccount,
+    params.tokenMint,
+    params.tokenProgram
+  );
+  const data = encodeInstruction(SurveyInstruction.ClaimReward, claimRewardSchema, {
+    survey_id: params.surveyId,
+    response_hash: bytes(params.responseHash),
+    // Only checked for ed25519 attestations, not for a co-signing verifier
+    expiry: params.expiry ?? BigInt(0),
+    reward: params.reward ?? RewardSelector.Both,
+    reward_tier: params.rewardTier ?? 0,
+    referrer: params.referrer ? bytes(params.referrer) : null,
+  });
+
+  const keys = [
+    { pubkey: params.participant, isSigner: true, isWritable: true },
+    { pubkey: params.surveyAccount, isSigner: false, isWritable: true },
+    { pubkey: participantAccount, isSigner: false, isWritable: true },
+    { pubkey: participantTokenAccount, isSigner: false, isWritable: true },
+    { pubkey: tokenPool, isSigner: false, isWritable: true },
+    { pubkey: params.tokenProgram, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: vaultAuthority, isSigner: false, isWritable: false },
+    params.expiry === undefined
+      ? { pubkey: params.verifier, isSigner: true, isWritable: false }
+      : { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: params.tokenMint, isSigner: false, isWritable: false },
+    { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  if (params.referrer) {
+    const [referrerAccount] = await deriveParticipantAddress(
+      params.surveyAccount,
+      params.referrer
+    );
+    keys.push({ pubkey: referrerAccount, isSigner: false, isWritable: true });
+  }
+
+  return new TransactionInstruction({
+    keys,
+    programId: PROGRAM_ID,
+    data,
   });
 }
 
-export function createDistributeNftInstruction(
-  owner: PublicKey,
-  surveyAccount: PublicKey,
-  participantAccount: PublicKey,
-  participantNftAccount: PublicKey,
-  nftMint: PublicKey,
-  surveyId: string
-): TransactionInstruction {
-  const data = serialize(
-    distributeNftSchema,
-    new DistributeNftData({ survey_id: surveyId })
+export async function createDistributeNftInstruction(params: {
+  authority: PublicKey;
+  surveyAccount: PublicKey;
+  participant: PublicKey;
+  surveyId: string;
+  soulbound?: boolean;
+  collectionMint?: PublicKey;
+}): Promise<TransactionInstruction> {
+  const [participantAccount] = await deriveParticipantAddress(
+    params.surveyAccount,
+    params.participant
   );
+  const [nftMint] = await deriveNftMintAddress(params.surveyAccount, params.participant);
+  const [vaultAuthority] = await deriveVaultAuthorityAddress(params.surveyAccount);
+  const tokenProgram = params.soulbound ? TOKEN_2022_PROGRAM_ID : TOKEN_PROGRAM_ID;
+  const participantNftAccount = getAssociatedTokenAddressSync(
+    nftMint,
+    params.participant,
+    false,
+    tokenProgram
+  );
+  const data = encodeInstruction(SurveyInstruction.DistributeNft, distributeNftSchema, {
+    survey_id: params.surveyId,
+  });
+
+  const keys = [
+    { pubkey: params.authority, isSigner: true, isWritable: true },
+    { pubkey: params.surveyAccount, isSigner: false, isWritable: false },
+    { pubkey: participantAccount, isSigner: false, isWritable: true },
+    { pubkey: params.participant, isSigner: false, isWritable: false },
+    { pubkey: nftMint, isSigner: false, isWritable: true },
+    { pubkey: participantNftAccount, isSigner: false, isWritable: true },
+    { pubkey: vaultAuthority, isSigner: false, isWritable: false },
+    { pubkey: tokenProgram, isSigner: false, isWritable: false },
+    { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+  ];
+  if (!params.soulbound) {
+    keys.push(
+      { pubkey: findMetadataAddress(nftMint), isSigner: false, isWritable: true },
+      { pubkey: findMasterEditionAddress(nftMint), isSigner: false, isWritable: true },
+      { pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false }
+    );
+    if (params.collectionMint) {
+      keys.push(
+        { pubkey: params.collectionMint, isSigner: false, isWritable: false },
+        { pubkey: findMetadataAddress(params.collectionMint), isSigner: false, isWritable: true },
+        {
+          pubkey: findMasterEditionAddress(params.collectionMint),
+          isSigner: false,
+          isWritable: false,
+        },
+        { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false }
+      );
+    }
+  }
 
   return new TransactionInstruction({
-    keys: [
-      { pubkey: owner, isSigner: true, isWritable: false },
-      { pubkey: surveyAccount, isSigner: false, isWritable: true },
-      { pubkey: participantAccount, isSigner: false, isWritable: true },
-      { pubkey: participantNftAccount, isSigner: false, isWritable: true },
-      { pubkey: nftMint, isSigner: false, isWritable: true },
-      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
-      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-      { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
-    ],
+    keys,
     programId: PROGRAM_ID,
-    data: Buffer.from(data),
+    data,
   });
 }
 
-export function createCloseSurveyInstruction(
-  owner: PublicKey,
-  surveyAccount: PublicKey,
-  ownerSolAccount: PublicKey,
-  surveyId: string
-): TransactionInstruction {
-  const data = serialize(
-    closeSurveySchema,
-    new CloseSurveyData({ survey_id: surveyId })
+export async function createCloseSurveyInstruction(params: {
+  owner: PublicKey;
+  surveyAccount: PublicKey;
+  ownerSolAccount: PublicKey;
+  tokenMint: PublicKey;
+  tokenProgram: PublicKey;
+  surveyId: string;
+  force?: boolean;
+}): Promise<TransactionInstruction> {
+  const [vaultAuthority] = await deriveVaultAuthorityAddress(params.surveyAccount);
+  const ownerTokenAccount = getAssociatedTokenAddressSync(
+    params.tokenMint,
+    params.owner,
+    false,
+    params.tokenProgram
   );
+  const tokenPool = await deriveTokenPoolAddress(
+    params.surveyAccount,
+    params.tokenMint,
+    params.tokenProgram
+  );
+  const data = encodeInstruction(SurveyInstruction.CloseSurvey, closeSurveySchema, {
+    survey_id: params.surveyId,
+    force: params.force ?? false,
+  });
+
+  // Token-2022 pools may hold withheld transfer fees, which close harvests to the mint
+  const mintWritable = params.tokenProgram.equals(TOKEN_2022_PROGRAM_ID);
 
   return new TransactionInstruction({
     keys: [
-      { pubkey: owner, isSigner: true, isWritable: false },
-      { pubkey: surveyAccount, isSigner: false, isWritable: true },
-      { pubkey: ownerSolAccount, isSigner: false, isWritable: true },
+      { pubkey: params.owner, isSigner: true, isWritable: false },
+      { pubkey: params.surveyAccount, isSigner: false, isWritable: true },
+      { pubkey: params.ownerSolAccount, isSigner: false, isWritable: true },
+      { pubkey: ownerTokenAccount, isSigner: false, isWritable: true },
+      { pubkey: tokenPool, isSigner: false, isWritable: true },
+      { pubkey: vaultAuthority, isSigner: false, isWritable: false },
+      { pubkey: params.tokenProgram, isSigner: false, isWritable: false },
+      { pubkey: params.tokenMint, isSigner: false, isWritable: mintWritable },
     ],
     programId: PROGRAM_ID,
-    data: Buffer.from(data),
+    data,
   });
-}
\ No newline at end of file
+}
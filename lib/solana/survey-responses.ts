@@ -0,0 +1,105 @@
+import fs from 'fs';
+import path from 'path';
+import crypto from 'crypto';
+import { PublicKey } from '@solana/web3.js';
+
+// Completed survey responses, one per participant and survey. The claim route
+// only attests to responses recorded here.
+interface SurveyResponseRecord {
+  responseHash: string;
+  answers: unknown;
+  submittedAt: number;
+}
+
+interface SurveyResponseData {
+  [key: string]: SurveyResponseRecord;
+}
+
+const STORE_DIR = path.join(process.cwd(), '.cache');
+const STORE_FILE = path.join(STORE_DIR, 'survey-responses.json');
+
+// DER prefix turning a raw 32-byte ed25519 public key into an SPKI key
+const ED25519_SPKI_PREFIX = Buffer.from('302a300506032b6570032100', 'hex');
+
+function ensureStoreDir() {
+  if (!fs.existsSync(STORE_DIR)) {
+    fs.mkdirSync(STORE_DIR, { recursive: true });
+  }
+}
+
+function readStore(): SurveyResponseData {
+  ensureStoreDir();
+
+  if (!fs.existsSync(STORE_FILE)) {
+    return {};
+  }
+
+  try {
+    return JSON.parse(fs.readFileSync(STORE_FILE, 'utf-8')) as SurveyResponseData;
+  } catch (error) {
+    console.error('Error reading survey responses:', error);
+    return {};
+  }
+}
+
+function writeStore(data: SurveyResponseData) {
+  ensureStoreDir();
+  fs.writeFileSync(STORE_FILE, JSON.stringify(data, null, 2), 'utf-8');
+}
+
+function responseKey(surveyAccount: PublicKey, participant: PublicKey): string {
+  return `${surveyAccount.toBase58()}:${participant.toBase58()}`;
+}
+
+// SHA-256 of the answers, the response hash recorded on the participant account
+export function hashSurveyResponse(answers: unknown): Buffer {
+  return crypto.createHash('sha256').update(JSON.stringify(answers)).digest();
+}
+
+// Check a wallet's ed25519 signature over `message`
+export function verifyWalletSignature(
+  wallet: PublicKey,
+  message: Buffer,
+  signature: Uint8Array
+): boolean {
+  try {
+    const key = crypto.createPublicKey({
+      key: Buffer.concat([ED25519_SPKI_PREFIX, wallet.toBuffer()]),
+      format: 'der',
+      type: 'spki',
+    });
+    return crypto.verify(null, message, key, signature);
+  } catch {
+    return false;
+  }
+}
+
+export function getSurveyResponse(
+  surveyAccount: PublicKey,
+  participant: PublicKey
+): SurveyResponseRecord | null {
+  return readStore()[responseKey(surveyAccount, participant)] ?? null;
+}
+
+// Record a participant's response. A recorded response is final, so every
+// claim leg is attested against the same response hash.
+export function saveSurveyResponse(
+  surveyAccount: PublicKey,
+  participant: PublicKey,
+  answers: unknown,
+  responseHash: Buffer
+): boolean {
+  const store = readStore();
+  const key = responseKey(surveyAccount, participant);
+  if (store[key]) {
+    return false;
+  }
+
+  store[key] = {
+    responseHash: responseHash.toString('hex'),
+    answers,
+    submittedAt: Date.now(),
+  };
+  writeStore(store);
+  return true;
+}
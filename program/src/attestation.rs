@@ -0,0 +1,92 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::UnixTimestamp,
+    ed25519_program,
+    program_error::ProgramError,
+    pubkey::{Pubkey, PUBKEY_BYTES},
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::error::SurveyError;
+
+/// Size of the message the verifier signs: survey, participant, response hash,
+/// expiry and reward tier
+pub const ATTESTATION_MESSAGE_LEN: usize = 32 + 32 + 32 + 8 + 1;
+
+// Layout of the ed25519 program's instruction data
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Build the message a survey verifier signs to authorize a claim
+pub fn attestation_message(
+    survey: &Pubkey,
+    participant: &Pubkey,
+    response_hash: &[u8; 32],
+    expiry: UnixTimestamp,
+    reward_tier: u8,
+) -> [u8; ATTESTATION_MESSAGE_LEN] {
+    let mut message = [0u8; ATTESTATION_MESSAGE_LEN];
+    message[0..32].copy_from_slice(survey.as_ref());
+    message[32..64].copy_from_slice(participant.as_ref());
+    message[64..96].copy_from_slice(response_hash);
+    message[96..104].copy_from_slice(&expiry.to_le_bytes());
+    message[104] = reward_tier;
+    message
+}
+
+/// Check that the instruction immediately before the current one is an
+/// ed25519 signature verification of `message` by `verifier`.
+///
+/// The ed25519 program fails the whole transaction if the signature is bad,
+/// so it is enough to confirm which key and message it checked.
+pub fn verify_ed25519_attestation(
+    instructions_sysvar: &AccountInfo,
+    verifier: &Pubkey,
+    message: &[u8],
+) -> Result<(), ProgramError> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    if current_index == 0 {
+        return Err(SurveyError::MissingAttestation.into());
+    }
+    
+    let ix = load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+    if ix.program_id != ed25519_program::id() {
+        return Err(SurveyError::MissingAttestation.into());
+    }
+    
+    let data = ix.data.as_slice();
+    if data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN || data[0] != 1 {
+        return Err(SurveyError::InvalidAttestation.into());
+    }
+    
+    let offsets = &data[SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN];
+    let read_u16 = |i: usize| u16::from_le_bytes([offsets[i], offsets[i + 1]]);
+    let signature_instruction_index = read_u16(2);
+    let public_key_offset = read_u16(4) as usize;
+    let public_key_instruction_index = read_u16(6);
+    let message_data_offset = read_u16(8) as usize;
+    let message_data_size = read_u16(10) as usize;
+    let message_instruction_index = read_u16(12);
+    
+    // Everything the ed25519 program checked must live in its own instruction data
+    if signature_instruction_index != CURRENT_INSTRUCTION
+        || public_key_instruction_index != CURRENT_INSTRUCTION
+        || message_instruction_index != CURRENT_INSTRUCTION
+    {
+        return Err(SurveyError::InvalidAttestation.into());
+    }
+    
+    let signed_key = data
+        .get(public_key_offset..public_key_offset + PUBKEY_BYTES)
+        .ok_or(SurveyError::InvalidAttestation)?;
+    let signed_message = data
+        .get(message_data_offset..message_data_offset + message_data_size)
+        .ok_or(SurveyError::InvalidAttestation)?;
+    
+    if signed_key != verifier.as_ref() || signed_message != message {
+        return Err(SurveyError::InvalidAttestation.into());
+    }
+    
+    Ok(())
+}
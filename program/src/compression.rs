@@ -0,0 +1,65 @@
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    pubkey,
+    pubkey::Pubkey,
+};
+
+/// SPL Account Compression program, which maintains concurrent Merkle trees
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey =
+    pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+/// SPL Noop program, used by account compression to log tree changes for indexers
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+// Anchor instruction discriminators, the first 8 bytes of sha256("global:<name>")
+const INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+const APPEND_DISCRIMINATOR: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+
+/// Build the leaf recorded for a participant's compressed NFT
+pub fn participation_leaf(
+    survey: &Pubkey,
+    participant: &Pubkey,
+    response_hash: &[u8; 32],
+) -> [u8; 32] {
+    keccak::hashv(&[survey.as_ref(), participant.as_ref(), response_hash]).to_bytes()
+}
+
+/// Build an account compression instruction initializing an empty tree
+/// in an account the caller has already allocated
+pub fn init_empty_merkle_tree(
+    merkle_tree: &Pubkey,
+    authority: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    let mut data = INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+    
+    Instruction {
+        program_id: ACCOUNT_COMPRESSION_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*merkle_tree, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build an account compression instruction appending `leaf` to a tree
+pub fn append(merkle_tree: &Pubkey, authority: &Pubkey, leaf: [u8; 32]) -> Instruction {
+    let mut data = APPEND_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&leaf);
+    
+    Instruction {
+        program_id: ACCOUNT_COMPRESSION_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*merkle_tree, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        ],
+        data,
+    }
+}
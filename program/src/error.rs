@@ -38,6 +38,162 @@ pub enum SurveyError {
     
     #[error("Invalid metadata")]
     InvalidMetadata,
+    
+    #[error("Account address does not match its derivation")]
+    InvalidAccountAddress,
+    
+    #[error("Invalid token pool")]
+    InvalidTokenPool,
+    
+    #[error("Participant account does not belong to this survey")]
+    ParticipantMismatch,
+    
+    #[error("Payout would leave the survey below its rent-exempt minimum")]
+    RentExemptionViolation,
+    
+    #[error("Claim is not authorized by the survey verifier")]
+    MissingAttestation,
+    
+    #[error("Invalid verifier attestation")]
+    InvalidAttestation,
+    
+    #[error("Verifier attestation has expired")]
+    AttestationExpired,
+    
+    #[error("Survey has not started")]
+    SurveyNotStarted,
+    
+    #[error("Survey claim deadline has passed")]
+    SurveyExpired,
+    
+    #[error("Invalid survey schedule")]
+    InvalidSchedule,
+    
+    #[error("Participants can still claim rewards")]
+    UnclaimedRewardsOutstanding,
+    
+    #[error("Survey is paused")]
+    SurveyPaused,
+    
+    #[error("Survey is not paused")]
+    SurveyNotPaused,
+    
+    #[error("Invalid survey update")]
+    InvalidUpdate,
+    
+    #[error("Update would leave funded rewards uncovered")]
+    UnderfundedUpdate,
+    
+    #[error("Signer is neither the survey owner nor an operator")]
+    Unauthorized,
+    
+    #[error("No pending owner for this signer")]
+    NotPendingOwner,
+    
+    #[error("Too many operators")]
+    TooManyOperators,
+    
+    #[error("Survey already has an NFT collection")]
+    CollectionAlreadySet,
+    
+    #[error("Survey already has a compressed NFT tree")]
+    NftTreeAlreadySet,
+    
+    #[error("Survey has no compressed NFT tree")]
+    MissingNftTree,
+    
+    #[error("Reward mint uses an unsupported Token-2022 extension")]
+    UnsupportedMintExtension,
+    
+    #[error("Token account is not the participant's associated token account for the reward mint")]
+    InvalidParticipantTokenAccount,
+    
+    #[error("Required signature is missing")]
+    MissingSigner,
+    
+    #[error("Account must be writable")]
+    AccountNotWritable,
+    
+    #[error("Account is not the expected program")]
+    InvalidProgramId,
+    
+    #[error("Account is not the expected sysvar")]
+    InvalidSysvar,
+    
+    #[error("Account is owned by an unexpected program")]
+    InvalidAccountOwner,
+    
+    #[error("Account data does not match the expected account type")]
+    InvalidAccountData,
+    
+    #[error("Account layout version is not supported")]
+    UnsupportedAccountVersion,
+    
+    #[error("Survey ID is empty, too long or contains unsupported characters")]
+    InvalidSurveyId,
+    
+    #[error("Reward tier does not exist or does not match the participant's tier")]
+    InvalidRewardTier,
+    
+    #[error("Reward tier has reached its participant cap")]
+    RewardTierFull,
+    
+    #[error("Too many reward tiers")]
+    TooManyRewardTiers,
+    
+    #[error("Reward curve needs a non-zero period and shares of at most 10000 basis points")]
+    InvalidRewardCurve,
+    
+    #[error("Survey is not a lottery")]
+    NotLottery,
+    
+    #[error("Participant has already entered the lottery")]
+    AlreadyEntered,
+    
+    #[error("Lottery entries are still open")]
+    LotteryStillOpen,
+    
+    #[error("Lottery winners have already been drawn")]
+    WinnersAlreadyDrawn,
+    
+    #[error("Lottery winners have not been drawn yet")]
+    WinnersNotDrawn,
+    
+    #[error("Revealed seed does not match the lottery commitment")]
+    InvalidSeedReveal,
+    
+    #[error("Participant was not drawn as a lottery winner")]
+    NotAWinner,
+    
+    #[error("Participants cannot refer themselves")]
+    SelfReferral,
+    
+    #[error("Participant was not referred by this referrer")]
+    InvalidReferrer,
+    
+    #[error("Referrer has been paid the maximum number of referral bonuses")]
+    ReferralLimitReached,
+    
+    #[error("Deposits do not cover every reward the survey has committed to")]
+    SurveyNotFunded,
+    
+    #[error("Referrer has not claimed a reward in this survey")]
+    ReferrerNotParticipant,
+    
+    #[error("Lottery draw has already been started")]
+    DrawAlreadyStarted,
+    
+    #[error("Lottery draw has not been started")]
+    DrawNotStarted,
+    
+    #[error("Slot whose hash completes the draw has not been reached")]
+    DrawSlotNotReached,
+    
+    #[error("The owner can still reveal the lottery seed")]
+    RevealWindowOpen,
+    
+    #[error("Survey has ended and takes no new claims")]
+    SurveyEnded,
 }
 
 impl From<SurveyError> for ProgramError {
@@ -1,69 +1,442 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{
-    instruction::{AccountMeta, Instruction},
-    program_error::ProgramError,
-    pubkey::Pubkey,
-    system_program,
-    sysvar,
-};
-use spl_token;
+use solana_program::{clock::UnixTimestamp, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::{DecayBasis, DecayKind};
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum SurveyInstruction {
     /// Initialize a new survey campaign
     /// 
+    /// Responses are accepted from `starts_at` until `ends_at`, and rewards
+    /// can be claimed until `claim_deadline`. The reward mint may belong to
+    /// SPL Token or Token-2022; Token-2022 mints may only use the extensions in
+    /// `token::SUPPORTED_MINT_EXTENSIONS`.
+    /// 
+    /// `survey_id` is either a plain ID of up to 32 ASCII letters, digits, `-`
+    /// and `_`, or, with `hash_survey_id`, a free-form title of up to
+    /// `state::MAX_SURVEY_TITLE_LEN` bytes whose SHA-256 hash is used as the
+    /// seed instead. Later instructions pass the same `survey_id` either way.
+    /// 
+    /// With `lottery_commitment`, the survey runs as a lottery: participants
+    /// enter through `EnterLottery` while it is open, and after `ends_at`
+    /// `StartLotteryDraw` and `DrawWinners` pick `max_participants` of the
+    /// entries, who are the only ones able to claim. The commitment is
+    /// `lottery::seed_commitment(seed)` of a secret seed the owner reveals to
+    /// start the draw.
+    /// 
     /// Accounts expected:
-    /// 0. `[signer]` The account initializing the survey (owner)
-    /// 1. `[writable]` The survey account to be created
+    /// 0. `[signer, writable]` The account initializing the survey (owner), pays for the survey account
+    /// 1. `[writable]` The survey account to be created, PDA of `["survey", owner, survey_id]`,
+    ///    or of `["survey", owner, sha256(survey_id)]` with `hash_survey_id`
     /// 2. `[]` The token mint for rewards
-    /// 3. `[writable]` The token account to hold reward tokens
+    /// 3. `[writable]` The token pool, the vault authority's associated token account for the mint
     /// 4. `[]` System program
     /// 5. `[]` Rent sysvar
+    /// 6. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 7. `[]` Token program that owns the mint, SPL Token or Token-2022
+    /// 8. `[]` Associated token account program
     InitializeSurvey {
         survey_id: String,
         sol_reward_amount: u64,
         token_reward_amount: u64,
         max_participants: u32,
+        verifier: Pubkey,
+        starts_at: UnixTimestamp,
+        ends_at: UnixTimestamp,
+        claim_deadline: UnixTimestamp,
+        hash_survey_id: bool,
+        lottery_commitment: Option<[u8; 32]>,
     },
     
     /// Claim rewards for completing a survey
     /// 
+    /// `reward` selects the SOL reward, the token reward or both. Each is paid
+    /// at most once and can be claimed in a separate transaction, so a failing
+    /// token transfer does not hold back the SOL reward; legs already claimed
+    /// are skipped, and a claim with nothing left to pay fails with
+    /// `AlreadyClaimed`. The participant takes one of the survey's
+    /// `max_participants` slots on their first claim, which must come by
+    /// `ends_at` unless they won a lottery; the remaining legs can be claimed
+    /// until `claim_deadline`.
+    /// 
+    /// The claim must be authorized by the survey verifier, either by having
+    /// the verifier co-sign the transaction or by placing an ed25519 program
+    /// instruction right before this one in which the verifier signs
+    /// `survey || participant || response_hash || expiry || reward_tier`.
+    /// Reward tokens are grossed up for any Token-2022 transfer fee so the
+    /// participant receives the tier's token reward.
+    /// 
+    /// `reward_tier` is 0 for the base reward or `n` for the survey's `n`th
+    /// reward tier. The verifier picks it, for example by response quality;
+    /// it is fixed on the first claim and every later claim must attest to the
+    /// same tier. The survey's reward curve scales the tier's rewards by how
+    /// early the first claim comes, and that share is kept for later claims.
+    /// 
+    /// `referrer` optionally names the participant who brought this one to
+    /// the survey. It is recorded on the first claim only, cannot be the
+    /// participant themselves, and lets the referrer collect the survey's
+    /// referral bonus through `ClaimReferralBonus`. The referrer must have
+    /// claimed in the survey already and is credited with at most
+    /// `max_per_referrer` referrals. Beyond the cap, and while it is zero, the
+    /// claim pays out as usual without recording the referral.
+    /// 
     /// Accounts expected:
-    /// 0. `[signer]` The participant claiming rewards
+    /// 0. `[signer, writable]` The participant claiming rewards, pays for the participant account
     /// 1. `[writable]` The survey account
-    /// 2. `[writable]` The participant account
-    /// 3. `[writable]` The participant's token account
-    /// 4. `[writable]` The survey's token account
+    /// 2. `[writable]` The participant account, PDA of `["participant", survey, participant]`
+    /// 3. `[writable]` The participant's associated token account for the reward mint, created if missing
+    /// 4. `[writable]` The survey's token pool
     /// 5. `[]` Token program
     /// 6. `[]` System program
+    /// 7. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 8. `[]` The verifier as `[signer]`, or the instructions sysvar
+    /// 9. `[]` The reward token mint
+    /// 10. `[]` Associated token account program
+    /// 11. `[writable]` The referrer's participant account, only when naming a `referrer`
     ClaimReward {
         survey_id: String,
+        response_hash: [u8; 32],
+        expiry: UnixTimestamp,
+        reward: RewardSelector,
+        reward_tier: u8,
+        referrer: Option<Pubkey>,
     },
     
     /// Distribute NFT to a participant
     /// 
+    /// Mints a one-of-one NFT on a fresh mint. By default it is an SPL Token
+    /// NFT with Token Metadata and a master edition; when the survey has an
+    /// `nft_collection`, the NFT is verified into it, which requires the vault
+    /// authority to be the collection's update authority. Surveys with
+    /// `soulbound_nfts` instead mint a non-transferable Token-2022 badge that
+    /// carries its metadata on the mint itself.
+    /// 
     /// Accounts expected:
-    /// 0. `[signer]` The survey owner
-    /// 1. `[writable]` The survey account
+    /// 0. `[signer, writable]` The survey owner or an operator, paying for the new accounts
+    /// 1. `[]` The survey account
     /// 2. `[writable]` The participant account
-    /// 3. `[writable]` The participant's NFT token account
-    /// 4. `[writable]` The NFT mint account
-    /// 5. `[]` Token program
-    /// 6. `[]` System program
-    /// 7. `[]` Rent sysvar
+    /// 3. `[]` The participant's wallet
+    /// 4. `[writable]` The NFT mint, PDA of `["nft_mint", survey, participant]`
+    /// 5. `[writable]` The participant's associated token account for the NFT mint
+    /// 6. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 7. `[]` Token program, or Token-2022 for soulbound badges
+    /// 8. `[]` Associated token account program
+    /// 9. `[]` System program
+    /// 10. `[]` Rent sysvar
+    /// 
+    /// Unless the survey mints soulbound badges:
+    /// 11. `[writable]` The NFT's metadata account
+    /// 12. `[writable]` The NFT's master edition account
+    /// 13. `[]` Token Metadata program
+    /// 
+    /// When the survey also has an `nft_collection`:
+    /// 14. `[]` The collection mint
+    /// 15. `[writable]` The collection's metadata account
+    /// 16. `[]` The collection's master edition account
+    /// 17. `[]` Instructions sysvar
     DistributeNft {
         survey_id: String,
     },
     
     /// Close a survey campaign
     /// 
+    /// Sweeps the token pool back to the owner, closes it, and shrinks the
+    /// survey account to a rent-exempt tombstone, returning the rest of its
+    /// SOL. The tombstone keeps the survey ID from being initialized again on
+    /// top of the closed campaign's participant accounts. Unless `force` is
+    /// set, closing is refused while the claim window is open and rewards
+    /// remain unclaimed.
+    /// 
     /// Accounts expected:
     /// 0. `[signer]` The survey owner
     /// 1. `[writable]` The survey account
     /// 2. `[writable]` The owner's SOL account (to receive rent)
+    /// 3. `[writable]` The owner's token account to receive the remaining reward tokens
+    /// 4. `[writable]` The survey's token pool
+    /// 5. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 6. `[]` Token program
+    /// 7. `[]` The reward token mint; `[writable]` when the pool holds withheld
+    ///    transfer fees, which are harvested to it
     CloseSurvey {
         survey_id: String,
+        force: bool,
+    },
+    
+    /// Deposit SOL and reward tokens into a survey's escrow
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The funder
+    /// 1. `[writable]` The survey account
+    /// 2. `[writable]` The funder's token account for the reward mint
+    /// 3. `[writable]` The survey's token pool
+    /// 4. `[]` Token program
+    /// 5. `[]` System program
+    /// 6. `[]` The reward token mint
+    FundSurvey {
+        survey_id: String,
+        sol_amount: u64,
+        token_amount: u64,
+    },
+    
+    /// Temporarily stop accepting claims, keeping funds and participant state
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The survey owner or an operator
+    /// 1. `[writable]` The survey account
+    PauseSurvey {
+        survey_id: String,
+    },
+    
+    /// Accept claims again after a pause
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The survey owner or an operator
+    /// 1. `[writable]` The survey account
+    ResumeSurvey {
+        survey_id: String,
+    },
+    
+    /// Change campaign parameters for future claims
+    /// 
+    /// Participant capacity and the schedule can only grow. If the survey's
+    /// deposits currently cover every remaining slot, the update must keep
+    /// them covered.
+    /// Reward tiers are replaced as a whole; a tier participants have claimed
+    /// at cannot be removed or capped below its participant count. A new
+    /// reward curve applies to participants who have not claimed yet. An
+    /// `nft_collection` can only be set while the survey has none.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The survey owner
+    /// 1. `[writable]` The survey account
+    /// 2. `[]` The reward token mint
+    UpdateSurvey {
+        survey_id: String,
+        update: SurveyUpdate,
+    },
+    
+    /// Propose a new owner; the transfer completes once they accept
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The survey owner
+    /// 1. `[writable]` The survey account
+    ProposeOwner {
+        survey_id: String,
+        new_owner: Pubkey,
+    },
+    
+    /// Accept a pending ownership transfer
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The proposed owner
+    /// 1. `[writable]` The survey account
+    AcceptOwner {
+        survey_id: String,
     },
+    
+    /// Replace the survey's delegated operators, who can distribute NFTs and
+    /// pause or resume the survey but cannot move funds
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The survey owner
+    /// 1. `[writable]` The survey account
+    SetOperators {
+        survey_id: String,
+        operators: Vec<Pubkey>,
+    },
+    
+    /// Mint a sized collection NFT for the survey and store it in `nft_collection`
+    /// 
+    /// The collection NFT is held by the vault authority, which is also its
+    /// update authority, so participation NFTs can be verified into it.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The survey owner, paying for the new accounts
+    /// 1. `[writable]` The survey account
+    /// 2. `[writable]` The collection mint, PDA of `["collection", survey]`
+    /// 3. `[writable]` The vault authority's associated token account for the collection mint
+    /// 4. `[writable]` The collection's metadata account
+    /// 5. `[writable]` The collection's master edition account
+    /// 6. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 7. `[]` Token program
+    /// 8. `[]` Associated token account program
+    /// 9. `[]` System program
+    /// 10. `[]` Rent sysvar
+    /// 11. `[]` Token Metadata program
+    CreateCollection {
+        survey_id: String,
+    },
+    
+    /// Set up a Merkle tree for compressed participation NFTs
+    /// 
+    /// The tree account must already be allocated for `max_depth` and
+    /// `max_buffer_size` and owned by the account compression program. The
+    /// vault authority becomes the tree authority.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The survey owner
+    /// 1. `[writable]` The survey account
+    /// 2. `[writable]` The Merkle tree account
+    /// 3. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 4. `[]` Account compression program
+    /// 5. `[]` Noop program
+    CreateNftTree {
+        survey_id: String,
+        max_depth: u32,
+        max_buffer_size: u32,
+    },
+    
+    /// Distribute a compressed NFT to a participant by appending a leaf to the survey's tree
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The survey owner or an operator
+    /// 1. `[writable]` The survey account
+    /// 2. `[writable]` The participant account
+    /// 3. `[writable]` The survey's Merkle tree account
+    /// 4. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 5. `[]` Account compression program
+    /// 6. `[]` Noop program
+    DistributeCompressedNft {
+        survey_id: String,
+    },
+    
+    /// Enter a lottery survey between `starts_at` and `ends_at`
+    /// 
+    /// The entry is authorized by the verifier the same way as a base-tier
+    /// `ClaimReward`, and creates the participant account holding the entry's
+    /// number. Winners claim through `ClaimReward` once winners are drawn.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The participant entering, pays for the participant account
+    /// 1. `[writable]` The survey account
+    /// 2. `[writable]` The participant account, PDA of `["participant", survey, participant]`
+    /// 3. `[]` System program
+    /// 4. `[]` The verifier as `[signer]`, or the instructions sysvar
+    EnterLottery {
+        survey_id: String,
+        response_hash: [u8; 32],
+        expiry: UnixTimestamp,
+    },
+    
+    /// Complete a started lottery draw with the hash of its draw slot
+    /// 
+    /// The draw seed is the seed the draw was started with hashed together
+    /// with the hash of the draw slot, or of the first slot produced after it
+    /// if it was skipped. Anyone can complete the draw once that slot is in
+    /// the SlotHashes sysvar. The draw slot is never replaced: if its hash has
+    /// aged out of the sysvar before anyone completed the draw, the seed the
+    /// draw was started with is used as the draw seed as is. Whoever can keep
+    /// the draw from being completed for the ~512 slots the hash stays in the
+    /// sysvar can therefore choose between those two outcomes, but no more.
+    /// 
+    /// Accounts expected:
+    /// 0. `[writable]` The survey account
+    /// 1. `[]` SlotHashes sysvar
+    DrawWinners {
+        survey_id: String,
+    },
+    
+    /// Pay a referrer the survey's referral bonus for a participant who named
+    /// them on their first claim
+    /// 
+    /// The referrer must hold a participant account of the survey. Each
+    /// referred participant earns a single bonus, paid at the amounts
+    /// configured when it is claimed; the per-referrer cap was applied when
+    /// the referral was recorded.
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The referrer, pays for their token account if missing
+    /// 1. `[writable]` The survey account
+    /// 2. `[writable]` The referrer's participant account
+    /// 3. `[writable]` The referred participant's account
+    /// 4. `[writable]` The referrer's associated token account for the reward mint, created if missing
+    /// 5. `[writable]` The survey's token pool
+    /// 6. `[]` Token program
+    /// 7. `[]` System program
+    /// 8. `[]` The vault authority, PDA of `["vault", survey]`
+    /// 9. `[]` The reward token mint
+    /// 10. `[]` Associated token account program
+    ClaimReferralBonus {
+        survey_id: String,
+    },
+    
+    /// Start the draw of a lottery survey after `ends_at`, fixing its seed and
+    /// pinning a draw slot `lottery::LOTTERY_DRAW_DELAY_SLOTS` ahead
+    /// 
+    /// `seed` must hash to the survey's `lottery_commitment`; anyone who knows
+    /// it can reveal it. If it has not been revealed within
+    /// `lottery::LOTTERY_REVEAL_WINDOW` seconds of `ends_at`, anyone can start
+    /// the draw without it, and the commitment stands in for the seed. Either
+    /// way the seed is fixed before the draw slot's hash exists, so the owner,
+    /// whether revealing or withholding, cannot tell which entries that will
+    /// favour, and entrants never learn the seed before entries close.
+    /// 
+    /// Accounts expected:
+    /// 0. `[writable]` The survey account
+    StartLotteryDraw {
+        survey_id: String,
+        seed: Option<[u8; 32]>,
+    },
+}
+
+/// Fields of a survey that can be changed by `UpdateSurvey`; `None` leaves a field as is
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct SurveyUpdate {
+    pub max_participants: Option<u32>,
+    pub sol_reward_amount: Option<u64>,
+    pub token_reward_amount: Option<u64>,
+    pub ends_at: Option<UnixTimestamp>,
+    pub claim_deadline: Option<UnixTimestamp>,
+    pub nft_collection: Option<Pubkey>,
+    pub nft_uri: Option<String>,
+    pub soulbound_nfts: Option<bool>,
+    pub reward_tiers: Option<Vec<RewardTierConfig>>,
+    pub reward_curve: Option<RewardCurveConfig>,
+    pub referral_bonus: Option<ReferralBonusConfig>,
+}
+
+/// A reward tier as configured through `UpdateSurvey`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RewardTierConfig {
+    pub sol_reward_amount: u64,
+    pub token_reward_amount: u64,
+    pub max_participants: u32,
+}
+
+/// Reward legs paid out by `ClaimReward`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardSelector {
+    Sol,
+    Token,
+    Both,
+}
+
+/// An early-bird reward curve as configured through `UpdateSurvey`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RewardCurveConfig {
+    pub kind: DecayKind,
+    pub basis: DecayBasis,
+    pub period: u64,
+    pub floor_bps: u16,
+    pub step_bps: u16,
+}
+
+/// A referral bonus as configured through `UpdateSurvey`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReferralBonusConfig {
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub max_per_referrer: u32,
+}
+
+impl RewardSelector {
+    pub fn includes_sol(self) -> bool {
+        matches!(self, RewardSelector::Sol | RewardSelector::Both)
+    }
+    
+    pub fn includes_token(self) -> bool {
+        matches!(self, RewardSelector::Token | RewardSelector::Both)
+    }
 }
 
 impl SurveyInstruction {
@@ -78,12 +451,23 @@ impl SurveyInstruction {
                     sol_reward_amount: payload.sol_reward_amount,
                     token_reward_amount: payload.token_reward_amount,
                     max_participants: payload.max_participants,
+                    verifier: payload.verifier,
+                    starts_at: payload.starts_at,
+                    ends_at: payload.ends_at,
+                    claim_deadline: payload.claim_deadline,
+                    hash_survey_id: payload.hash_survey_id,
+                    lottery_commitment: payload.lottery_commitment,
                 })
             }
             1 => {
                 let payload = ClaimRewardPayload::try_from_slice(rest)?;
                 Ok(SurveyInstruction::ClaimReward {
                     survey_id: payload.survey_id,
+                    response_hash: payload.response_hash,
+                    expiry: payload.expiry,
+                    reward: payload.reward,
+                    reward_tier: payload.reward_tier,
+                    referrer: payload.referrer,
                 })
             }
             2 => {
@@ -96,6 +480,101 @@ impl SurveyInstruction {
                 let payload = CloseSurveyPayload::try_from_slice(rest)?;
                 Ok(SurveyInstruction::CloseSurvey {
                     survey_id: payload.survey_id,
+                    force: payload.force,
+                })
+            }
+            4 => {
+                let payload = FundSurveyPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::FundSurvey {
+                    survey_id: payload.survey_id,
+                    sol_amount: payload.sol_amount,
+                    token_amount: payload.token_amount,
+                })
+            }
+            5 => {
+                let payload = PauseSurveyPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::PauseSurvey {
+                    survey_id: payload.survey_id,
+                })
+            }
+            6 => {
+                let payload = ResumeSurveyPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::ResumeSurvey {
+                    survey_id: payload.survey_id,
+                })
+            }
+            7 => {
+                let payload = UpdateSurveyPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::UpdateSurvey {
+                    survey_id: payload.survey_id,
+                    update: payload.update,
+                })
+            }
+            8 => {
+                let payload = ProposeOwnerPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::ProposeOwner {
+                    survey_id: payload.survey_id,
+                    new_owner: payload.new_owner,
+                })
+            }
+            9 => {
+                let payload = AcceptOwnerPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::AcceptOwner {
+                    survey_id: payload.survey_id,
+                })
+            }
+            10 => {
+                let payload = SetOperatorsPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::SetOperators {
+                    survey_id: payload.survey_id,
+                    operators: payload.operators,
+                })
+            }
+            11 => {
+                let payload = CreateCollectionPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::CreateCollection {
+                    survey_id: payload.survey_id,
+                })
+            }
+            12 => {
+                let payload = CreateNftTreePayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::CreateNftTree {
+                    survey_id: payload.survey_id,
+                    max_depth: payload.max_depth,
+                    max_buffer_size: payload.max_buffer_size,
+                })
+            }
+            13 => {
+                let payload = DistributeCompressedNftPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::DistributeCompressedNft {
+                    survey_id: payload.survey_id,
+                })
+            }
+            14 => {
+                let payload = EnterLotteryPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::EnterLottery {
+                    survey_id: payload.survey_id,
+                    response_hash: payload.response_hash,
+                    expiry: payload.expiry,
+                })
+            }
+            15 => {
+                let payload = DrawWinnersPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::DrawWinners {
+                    survey_id: payload.survey_id,
+                })
+            }
+            16 => {
+                let payload = ClaimReferralBonusPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::ClaimReferralBonus {
+                    survey_id: payload.survey_id,
+                })
+            }
+            17 => {
+                let payload = StartLotteryDrawPayload::try_from_slice(rest)?;
+                Ok(SurveyInstruction::StartLotteryDraw {
+                    survey_id: payload.survey_id,
+                    seed: payload.seed,
                 })
             }
             _ => Err(ProgramError::InvalidInstructionData),
@@ -109,11 +588,22 @@ struct InitializeSurveyPayload {
     sol_reward_amount: u64,
     token_reward_amount: u64,
     max_participants: u32,
+    verifier: Pubkey,
+    starts_at: UnixTimestamp,
+    ends_at: UnixTimestamp,
+    claim_deadline: UnixTimestamp,
+    hash_survey_id: bool,
+    lottery_commitment: Option<[u8; 32]>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 struct ClaimRewardPayload {
     survey_id: String,
+    response_hash: [u8; 32],
+    expiry: UnixTimestamp,
+    reward: RewardSelector,
+    reward_tier: u8,
+    referrer: Option<Pubkey>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -124,4 +614,85 @@ struct DistributeNftPayload {
 #[derive(BorshSerialize, BorshDeserialize)]
 struct CloseSurveyPayload {
     survey_id: String,
+    force: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct FundSurveyPayload {
+    survey_id: String,
+    sol_amount: u64,
+    token_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct PauseSurveyPayload {
+    survey_id: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ResumeSurveyPayload {
+    survey_id: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct UpdateSurveyPayload {
+    survey_id: String,
+    update: SurveyUpdate,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ProposeOwnerPayload {
+    survey_id: String,
+    new_owner: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct AcceptOwnerPayload {
+    survey_id: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SetOperatorsPayload {
+    survey_id: String,
+    operators: Vec<Pubkey>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct CreateCollectionPayload {
+    survey_id: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct CreateNftTreePayload {
+    survey_id: String,
+    max_depth: u32,
+    max_buffer_size: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct DistributeCompressedNftPayload {
+    survey_id: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct EnterLotteryPayload {
+    survey_id: String,
+    response_hash: [u8; 32],
+    expiry: UnixTimestamp,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct DrawWinnersPayload {
+    survey_id: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ClaimReferralBonusPayload {
+    survey_id: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct StartLotteryDrawPayload {
+    survey_id: String,
+    seed: Option<[u8; 32]>,
 }
\ No newline at end of file
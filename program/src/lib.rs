@@ -1,25 +1,22 @@
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::AccountInfo,
     entrypoint,
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
-    program_error::ProgramError,
     pubkey::Pubkey,
-    rent::Rent,
-    system_instruction,
-    sysvar::Sysvar,
 };
-use spl_associated_token_account::instruction::create_associated_token_account;
-use spl_token::instruction::{mint_to, transfer};
 
+pub mod attestation;
+pub mod compression;
 pub mod error;
 pub mod instruction;
+pub mod lottery;
+pub mod pda;
 pub mod processor;
 pub mod state;
+pub mod token;
+pub mod validation;
 
-use crate::error::SurveyError;
 use crate::instruction::SurveyInstruction;
 use crate::processor::Processor;
 
@@ -38,6 +35,12 @@ pub fn process_instruction(
             sol_reward_amount,
             token_reward_amount,
             max_participants,
+            verifier,
+            starts_at,
+            ends_at,
+            claim_deadline,
+            hash_survey_id,
+            lottery_commitment,
         } => {
             msg!("Instruction: InitializeSurvey");
             Processor::process_initialize_survey(
@@ -47,19 +50,115 @@ pub fn process_instruction(
                 sol_reward_amount,
                 token_reward_amount,
                 max_participants,
+                verifier,
+                starts_at,
+                ends_at,
+                claim_deadline,
+                hash_survey_id,
+                lottery_commitment,
             )
         }
-        SurveyInstruction::ClaimReward { survey_id } => {
+        SurveyInstruction::ClaimReward {
+            survey_id,
+            response_hash,
+            expiry,
+            reward,
+            reward_tier,
+            referrer,
+        } => {
             msg!("Instruction: ClaimReward");
-            Processor::process_claim_reward(accounts, program_id, survey_id)
+            Processor::process_claim_reward(
+                accounts,
+                program_id,
+                survey_id,
+                response_hash,
+                expiry,
+                reward,
+                reward_tier,
+                referrer,
+            )
         }
         SurveyInstruction::DistributeNft { survey_id } => {
             msg!("Instruction: DistributeNft");
             Processor::process_distribute_nft(accounts, program_id, survey_id)
         }
-        SurveyInstruction::CloseSurvey { survey_id } => {
+        SurveyInstruction::CloseSurvey { survey_id, force } => {
             msg!("Instruction: CloseSurvey");
-            Processor::process_close_survey(accounts, program_id, survey_id)
+            Processor::process_close_survey(accounts, program_id, survey_id, force)
+        }
+        SurveyInstruction::FundSurvey {
+            survey_id,
+            sol_amount,
+            token_amount,
+        } => {
+            msg!("Instruction: FundSurvey");
+            Processor::process_fund_survey(accounts, program_id, survey_id, sol_amount, token_amount)
+        }
+        SurveyInstruction::PauseSurvey { survey_id } => {
+            msg!("Instruction: PauseSurvey");
+            Processor::process_pause_survey(accounts, program_id, survey_id)
+        }
+        SurveyInstruction::ResumeSurvey { survey_id } => {
+            msg!("Instruction: ResumeSurvey");
+            Processor::process_resume_survey(accounts, program_id, survey_id)
+        }
+        SurveyInstruction::UpdateSurvey { survey_id, update } => {
+            msg!("Instruction: UpdateSurvey");
+            Processor::process_update_survey(accounts, program_id, survey_id, update)
+        }
+        SurveyInstruction::ProposeOwner { survey_id, new_owner } => {
+            msg!("Instruction: ProposeOwner");
+            Processor::process_propose_owner(accounts, program_id, survey_id, new_owner)
+        }
+        SurveyInstruction::AcceptOwner { survey_id } => {
+            msg!("Instruction: AcceptOwner");
+            Processor::process_accept_owner(accounts, program_id, survey_id)
+        }
+        SurveyInstruction::SetOperators { survey_id, operators } => {
+            msg!("Instruction: SetOperators");
+            Processor::process_set_operators(accounts, program_id, survey_id, operators)
+        }
+        SurveyInstruction::CreateCollection { survey_id } => {
+            msg!("Instruction: CreateCollection");
+            Processor::process_create_collection(accounts, program_id, survey_id)
+        }
+        SurveyInstruction::CreateNftTree {
+            survey_id,
+            max_depth,
+            max_buffer_size,
+        } => {
+            msg!("Instruction: CreateNftTree");
+            Processor::process_create_nft_tree(
+                accounts,
+                program_id,
+                survey_id,
+                max_depth,
+                max_buffer_size,
+            )
+        }
+        SurveyInstruction::DistributeCompressedNft { survey_id } => {
+            msg!("Instruction: DistributeCompressedNft");
+            Processor::process_distribute_compressed_nft(accounts, program_id, survey_id)
+        }
+        SurveyInstruction::EnterLottery {
+            survey_id,
+            response_hash,
+            expiry,
+        } => {
+            msg!("Instruction: EnterLottery");
+            Processor::process_enter_lottery(accounts, program_id, survey_id, response_hash, expiry)
+        }
+        SurveyInstruction::DrawWinners { survey_id } => {
+            msg!("Instruction: DrawWinners");
+            Processor::process_draw_winners(accounts, program_id, survey_id)
+        }
+        SurveyInstruction::ClaimReferralBonus { survey_id } => {
+            msg!("Instruction: ClaimReferralBonus");
+            Processor::process_claim_referral_bonus(accounts, program_id, survey_id)
+        }
+        SurveyInstruction::StartLotteryDraw { survey_id, seed } => {
+            msg!("Instruction: StartLotteryDraw");
+            Processor::process_start_lottery_draw(accounts, program_id, survey_id, seed)
         }
     }
 }
\ No newline at end of file
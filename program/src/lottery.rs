@@ -0,0 +1,110 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::{Slot, UnixTimestamp},
+    hash::{hash, hashv},
+    program_error::ProgramError,
+};
+
+use crate::error::SurveyError;
+
+/// Slots between starting a draw and the slot whose hash completes it, so the
+/// hash is still unknown when the seed is revealed
+pub const LOTTERY_DRAW_DELAY_SLOTS: Slot = 8;
+
+/// Seconds after `ends_at` during which only the owner's revealed seed can
+/// start the draw
+pub const LOTTERY_REVEAL_WINDOW: UnixTimestamp = 86_400;
+
+// Layout of the SlotHashes sysvar: a u64 entry count followed by
+// `(slot: u64, hash: [u8; 32])` entries, most recent first
+const SLOT_HASHES_HEADER_LEN: usize = 8;
+const SLOT_HASHES_ENTRY_LEN: usize = 40;
+
+// Feistel rounds of the permutation that orders entries
+const PERMUTATION_ROUNDS: u8 = 4;
+
+/// Commitment an owner publishes when creating a lottery survey, `sha256(seed)`
+pub fn seed_commitment(seed: &[u8; 32]) -> [u8; 32] {
+    hash(seed).to_bytes()
+}
+
+/// Hash of the first slot at or after `draw_slot` in the SlotHashes sysvar,
+/// so a skipped `draw_slot` resolves to the next slot that was produced.
+/// `None` once that slot has aged out of the sysvar and can no longer be told
+/// apart from later ones; fails with `DrawSlotNotReached` before it exists.
+pub fn draw_slot_hash(
+    slot_hashes: &AccountInfo,
+    draw_slot: Slot,
+) -> Result<Option<[u8; 32]>, ProgramError> {
+    let data = slot_hashes.try_borrow_data()?;
+    let count = data
+        .get(..SLOT_HASHES_HEADER_LEN)
+        .map(|count| u64::from_le_bytes(count.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidAccountData)?;
+    
+    // Entries run from the newest slot back, so the last one at or after
+    // `draw_slot` is the earliest, known for sure once an older one follows
+    let mut candidate = None;
+    for index in 0..count as usize {
+        let start = SLOT_HASHES_HEADER_LEN + index * SLOT_HASHES_ENTRY_LEN;
+        let entry = data
+            .get(start..start + SLOT_HASHES_ENTRY_LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+        let slot_hash: [u8; 32] = entry[8..].try_into().unwrap();
+        
+        if slot < draw_slot {
+            return candidate
+                .map(Some)
+                .ok_or_else(|| SurveyError::DrawSlotNotReached.into());
+        }
+        if slot == draw_slot {
+            return Ok(Some(slot_hash));
+        }
+        candidate = Some(slot_hash);
+    }
+    
+    match candidate {
+        Some(_) => Ok(None),
+        None => Err(SurveyError::DrawSlotNotReached.into()),
+    }
+}
+
+/// Randomness of a draw: the seed the draw was started with, fixed before the
+/// slot hash existed, mixed with that slot hash
+pub fn draw_seed(seed: &[u8; 32], slot_hash: &[u8; 32]) -> [u8; 32] {
+    hashv(&[seed, slot_hash]).to_bytes()
+}
+
+/// Position of `entry` in a pseudo-random ordering of `0..entries` keyed by
+/// `seed`. The ordering is a permutation, so taking the first `winners`
+/// positions picks exactly `min(winners, entries)` distinct entries, and any
+/// entry's position can be computed without looking at the others. `entry`
+/// must be below `entries`.
+pub fn draw_position(seed: &[u8; 32], entry: u32, entries: u32) -> u32 {
+    // A Feistel network permutes `0..2^(2 * half_bits)`; walking the cycle
+    // until the value lands below `entries` restricts it to `0..entries`
+    let bits = u32::BITS - entries.saturating_sub(1).leading_zeros();
+    let half_bits = bits.div_ceil(2);
+    let mask = (1u64 << half_bits) - 1;
+    
+    let mut value = entry as u64;
+    loop {
+        let (mut left, mut right) = (value >> half_bits, value & mask);
+        for round in 0..PERMUTATION_ROUNDS {
+            let digest = hashv(&[seed, &[round], &right.to_le_bytes()]).to_bytes();
+            let mixed = u64::from_le_bytes(digest[..8].try_into().unwrap()) & mask;
+            (left, right) = (right, left ^ mixed);
+        }
+        value = (left << half_bits) | right;
+        
+        if value < entries as u64 {
+            return value as u32;
+        }
+    }
+}
+
+/// Whether `entry` is among the `winners` drawn from `entries`
+pub fn is_winner(seed: &[u8; 32], entry: u32, entries: u32, winners: u32) -> bool {
+    entry < entries && draw_position(seed, entry, entries) < winners
+}
@@ -0,0 +1,97 @@
+use solana_program::{hash::hash, program_error::ProgramError, pubkey::Pubkey};
+
+pub const SURVEY_SEED: &[u8] = b"survey";
+pub const PARTICIPANT_SEED: &[u8] = b"participant";
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const NFT_MINT_SEED: &[u8] = b"nft_mint";
+pub const COLLECTION_SEED: &[u8] = b"collection";
+
+/// Derive the survey account address from `["survey", owner, survey_id]`
+pub fn find_survey_address(program_id: &Pubkey, owner: &Pubkey, survey_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SURVEY_SEED, owner.as_ref(), survey_id.as_bytes()],
+        program_id,
+    )
+}
+
+/// Seed of a survey keyed by a free-form title, `sha256(title)`
+pub fn hashed_survey_id(title: &str) -> [u8; 32] {
+    hash(title.as_bytes()).to_bytes()
+}
+
+/// Derive the address of a survey keyed by `sha256(title)`, from
+/// `["survey", owner, sha256(title)]`
+pub fn find_hashed_survey_address(program_id: &Pubkey, owner: &Pubkey, title: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SURVEY_SEED, owner.as_ref(), &hashed_survey_id(title)],
+        program_id,
+    )
+}
+
+/// Re-create the survey account address from its stored bump
+pub fn survey_address(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    survey_id: &[u8],
+    bump: u8,
+) -> Result<Pubkey, ProgramError> {
+    Pubkey::create_program_address(
+        &[SURVEY_SEED, owner.as_ref(), survey_id, &[bump]],
+        program_id,
+    )
+    .map_err(|_| ProgramError::InvalidSeeds)
+}
+
+/// Derive the participant account address from `["participant", survey, participant]`
+pub fn find_participant_address(
+    program_id: &Pubkey,
+    survey: &Pubkey,
+    participant: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PARTICIPANT_SEED, survey.as_ref(), participant.as_ref()],
+        program_id,
+    )
+}
+
+/// Re-create the participant account address from its stored bump
+pub fn participant_address(
+    program_id: &Pubkey,
+    survey: &Pubkey,
+    participant: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, ProgramError> {
+    Pubkey::create_program_address(
+        &[PARTICIPANT_SEED, survey.as_ref(), participant.as_ref(), &[bump]],
+        program_id,
+    )
+    .map_err(|_| ProgramError::InvalidSeeds)
+}
+
+/// Derive the vault authority that owns a survey's token pool from `["vault", survey]`
+pub fn find_vault_authority_address(program_id: &Pubkey, survey: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, survey.as_ref()], program_id)
+}
+
+/// Re-create the vault authority address from its stored bump
+pub fn vault_authority_address(
+    program_id: &Pubkey,
+    survey: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, ProgramError> {
+    Pubkey::create_program_address(&[VAULT_SEED, survey.as_ref(), &[bump]], program_id)
+        .map_err(|_| ProgramError::InvalidSeeds)
+}
+
+/// Derive a participant's NFT mint from `["nft_mint", survey, participant]`
+pub fn find_nft_mint_address(program_id: &Pubkey, survey: &Pubkey, participant: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[NFT_MINT_SEED, survey.as_ref(), participant.as_ref()],
+        program_id,
+    )
+}
+
+/// Derive a survey's collection NFT mint from `["collection", survey]`
+pub fn find_collection_mint_address(program_id: &Pubkey, survey: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[COLLECTION_SEED, survey.as_ref()], program_id)
+}
@@ -1,26 +1,75 @@
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    clock::Clock,
+    clock::{Clock, Epoch, UnixTimestamp},
     entrypoint::ProgramResult,
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
-    system_instruction,
-    sysvar::Sysvar,
+    system_instruction, system_program,
+    sysvar::{self, Sysvar},
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use mpl_token_metadata::{
+    instructions::{
+        CreateMasterEditionV3CpiBuilder, CreateMetadataAccountV3CpiBuilder,
+        VerifyCollectionV1CpiBuilder,
+    },
+    types::{Collection, CollectionDetails, DataV2},
+};
+use spl_token::instruction::{initialize_mint2, mint_to};
+use spl_token_2022::{
+    extension::{
+        metadata_pointer, transfer_fee::instruction::harvest_withheld_tokens_to_mint,
+        ExtensionType,
+    },
+    instruction::{close_account, initialize_non_transferable_mint, transfer_checked, AuthorityType},
+};
+use spl_pod::optional_keys::OptionalNonZeroPubkey;
+use spl_token_metadata_interface::{
+    instruction::initialize as initialize_token_metadata, state::TokenMetadata,
 };
-use spl_token::instruction::{mint_to, transfer};
 
 use crate::{
+    attestation::{attestation_message, verify_ed25519_attestation, ATTESTATION_MESSAGE_LEN},
+    compression::{
+        append, init_empty_merkle_tree, participation_leaf, ACCOUNT_COMPRESSION_PROGRAM_ID,
+        NOOP_PROGRAM_ID,
+    },
     error::SurveyError,
-    state::{ParticipantAccount, SurveyAccount},
+    instruction::{RewardSelector, SurveyUpdate},
+    lottery::{
+        draw_seed, draw_slot_hash, seed_commitment, LOTTERY_DRAW_DELAY_SLOTS,
+        LOTTERY_REVEAL_WINDOW,
+    },
+    pda::{
+        find_collection_mint_address, find_nft_mint_address, find_participant_address,
+        find_vault_authority_address, hashed_survey_id, vault_authority_address,
+        COLLECTION_SEED, NFT_MINT_SEED, PARTICIPANT_SEED, SURVEY_SEED, VAULT_SEED,
+    },
+    state::{
+        apply_reward_bps, nft_name, ParticipantAccount, ReferralBonus, RewardCurve, RewardTier,
+        SurveyAccount, CLOSED_SURVEY_DISCRIMINATOR, DISCRIMINATOR_LEN, NFT_SYMBOL,
+    },
+    token::{
+        gross_transfer_amount, reward_mint_decimals, token_account_amount,
+        token_account_mint_and_owner, withheld_transfer_fees,
+    },
+    validation::{
+        check_account_owner, check_program, check_signer, check_survey_id, check_sysvar,
+        check_token_program, check_writable, init_account, load_participant_mut, load_survey, load_survey_mut,
+    },
 };
 
 pub struct Processor;
 
 impl Processor {
+    #[allow(clippy::too_many_arguments)]
     pub fn process_initialize_survey(
         accounts: &[AccountInfo],
         program_id: &Pubkey,
@@ -28,6 +77,12 @@ impl Processor {
         sol_reward_amount: u64,
         token_reward_amount: u64,
         max_participants: u32,
+        verifier: Pubkey,
+        starts_at: UnixTimestamp,
+        ends_at: UnixTimestamp,
+        claim_deadline: UnixTimestamp,
+        hash_survey_id: bool,
+        lottery_commitment: Option<[u8; 32]>,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         
@@ -37,39 +92,143 @@ impl Processor {
         let token_pool = next_account_info(account_info_iter)?;
         let system_program = next_account_info(account_info_iter)?;
         let rent_sysvar = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let associated_token_program = next_account_info(account_info_iter)?;
+        
+        check_signer(owner)?;
+        check_writable(owner)?;
+        check_writable(survey_account)?;
+        check_writable(token_pool)?;
+        check_program(system_program, &system_program::id())?;
+        check_sysvar(rent_sysvar, &sysvar::rent::id())?;
+        check_token_program(token_program)?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+        
+        check_survey_id(&survey_id, hash_survey_id)?;
+        let survey_seed = if hash_survey_id {
+            hashed_survey_id(&survey_id).to_vec()
+        } else {
+            survey_id.as_bytes().to_vec()
+        };
+        
+        let (survey_address, bump) = Pubkey::find_program_address(
+            &[SURVEY_SEED, owner.key.as_ref(), &survey_seed],
+            program_id,
+        );
+        if survey_address != *survey_account.key {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
         
-        if !owner.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        if !survey_account.data_is_empty() {
+            return Err(SurveyError::SurveyAlreadyExists.into());
         }
         
         let rent = &Rent::from_account_info(rent_sysvar)?;
         
-        if !rent.is_exempt(survey_account.lamports(), SurveyAccount::LEN) {
-            return Err(SurveyError::InsufficientFunds.into());
+        if max_participants == 0 {
+            return Err(SurveyError::InvalidRewardAmount.into());
+        }
+        
+        if starts_at >= ends_at || ends_at > claim_deadline {
+            return Err(SurveyError::InvalidSchedule.into());
+        }
+        
+        create_pda_account(
+            owner,
+            survey_account,
+            SurveyAccount::LEN,
+            program_id,
+            system_program,
+            rent,
+            &[SURVEY_SEED, owner.key.as_ref(), &survey_seed, &[bump]],
+        )?;
+        
+        // The token pool is owned by the vault authority PDA so claims can sign for it
+        let (vault_address, vault_bump) =
+            find_vault_authority_address(program_id, survey_account.key);
+        if vault_address != *vault_authority.key {
+            return Err(SurveyError::InvalidAccountAddress.into());
         }
         
+        check_account_owner(token_mint, token_program.key)?;
+        let token_decimals = reward_mint_decimals(token_mint)?;
+        
+        if get_associated_token_address_with_program_id(
+            vault_authority.key,
+            token_mint.key,
+            token_program.key,
+        ) != *token_pool.key
+        {
+            return Err(SurveyError::InvalidTokenPool.into());
+        }
+        
+        invoke(
+            &create_associated_token_account_idempotent(
+                owner.key,
+                vault_authority.key,
+                token_mint.key,
+                token_program.key,
+            ),
+            &[
+                owner.clone(),
+                token_pool.clone(),
+                vault_authority.clone(),
+                token_mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+        
         let clock = Clock::get()?;
         
-        let survey = SurveyAccount::new(
-            survey_id.clone(),
+        let mut survey = SurveyAccount::new(
+            &survey_seed,
             *owner.key,
+            verifier,
             sol_reward_amount,
             token_reward_amount,
             *token_mint.key,
+            *token_program.key,
+            token_decimals,
             max_participants,
             clock.unix_timestamp,
-        );
+            starts_at,
+            ends_at,
+            claim_deadline,
+            bump,
+            vault_bump,
+        )?;
+        survey.hashed_survey_id = hash_survey_id.into();
+        
+        if let Some(commitment) = lottery_commitment {
+            survey.is_lottery = true.into();
+            survey.lottery_commitment = commitment;
+        }
+        
+        // Reject campaigns whose total commitment could never be funded
+        let gross = |amount| gross_transfer_amount(token_mint, amount, clock.epoch).ok();
+        if survey.outstanding_sol().is_none() || survey.outstanding_tokens(gross).is_none() {
+            return Err(SurveyError::InvalidRewardAmount.into());
+        }
         
-        survey.serialize(&mut &mut survey_account.data.borrow_mut()[..])?;
+        init_account(survey_account, &survey)?;
         
         msg!("Survey initialized: {}", survey_id);
         Ok(())
     }
     
+    #[allow(clippy::too_many_arguments)]
     pub fn process_claim_reward(
         accounts: &[AccountInfo],
         program_id: &Pubkey,
         survey_id: String,
+        response_hash: [u8; 32],
+        expiry: UnixTimestamp,
+        reward: RewardSelector,
+        reward_tier: u8,
+        referrer: Option<Pubkey>,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         
@@ -80,72 +239,215 @@ impl Processor {
         let survey_token_account = next_account_info(account_info_iter)?;
         let token_program = next_account_info(account_info_iter)?;
         let system_program = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
+        let verifier_or_instructions = next_account_info(account_info_iter)?;
+        let token_mint = next_account_info(account_info_iter)?;
+        let associated_token_program = next_account_info(account_info_iter)?;
         
-        if !participant.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        
-        let mut survey = SurveyAccount::try_from_slice(&survey_account.data.borrow())?;
+        check_signer(participant)?;
+        check_writable(participant)?;
+        check_writable(participant_account)?;
+        check_program(system_program, &system_program::id())?;
         
-        if !survey.is_initialized {
-            return Err(SurveyError::NotInitialized.into());
-        }
+        let mut survey = load_survey_mut(program_id, survey_account)?;
         
-        if survey.survey_id != survey_id {
+        if !survey.matches_survey_id(&survey_id) {
             return Err(SurveyError::SurveyNotFound.into());
         }
         
-        if !survey.is_active {
+        if !bool::from(survey.is_active) {
             return Err(SurveyError::SurveyClosed.into());
         }
         
-        // Check if participant has already claimed
-        let mut participant_data = ParticipantAccount::try_from_slice(&participant_account.data.borrow())
-            .unwrap_or(ParticipantAccount::new(survey_id.clone(), *participant.key));
-        
-        if participant_data.has_claimed_sol || participant_data.has_claimed_token {
-            return Err(SurveyError::AlreadyClaimed.into());
+        if bool::from(survey.is_paused) {
+            return Err(SurveyError::SurveyPaused.into());
         }
         
-        // Transfer SOL reward
-        if survey.sol_reward_amount > 0 {
-            **survey_account.lamports.borrow_mut() -= survey.sol_reward_amount;
-            **participant.lamports.borrow_mut() += survey.sol_reward_amount;
-            participant_data.has_claimed_sol = true;
+        let clock = Clock::get()?;
+        survey.check_claim_window(clock.unix_timestamp)?;
+        
+        // Nothing is paid until the escrow covers the whole campaign
+        if *token_mint.key != survey.token_mint {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        if !survey.is_funded(|amount| gross_transfer_amount(token_mint, amount, clock.epoch).ok()) {
+            return Err(SurveyError::SurveyNotFunded.into());
         }
         
-        // Transfer token reward
-        if survey.token_reward_amount > 0 {
-            let transfer_ix = transfer(
-                &spl_token::id(),
-                survey_token_account.key,
-                participant_token_account.key,
+        check_verifier_authorization(
+            &survey.verifier,
+            verifier_or_instructions,
+            &attestation_message(
                 survey_account.key,
-                &[],
-                survey.token_reward_amount,
-            )?;
+                participant.key,
+                &response_hash,
+                expiry,
+                reward_tier,
+            ),
+            expiry,
+            clock.unix_timestamp,
+        )?;
+        
+        let (participant_address, participant_bump) =
+            find_participant_address(program_id, survey_account.key, participant.key);
+        if participant_address != *participant_account.key {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        // Lottery surveys only pay entries drawn as winners
+        let is_lottery = bool::from(survey.is_lottery);
+        if is_lottery && !bool::from(survey.winners_drawn) {
+            return Err(SurveyError::WinnersNotDrawn.into());
+        }
+        
+        let rent = Rent::get()?;
+        
+        // Create the participant account on first claim, then load it in place
+        if participant_account.data_is_empty() {
+            if is_lottery {
+                return Err(SurveyError::NotAWinner.into());
+            }
             
-            invoke(
-                &transfer_ix,
+            create_pda_account(
+                participant,
+                participant_account,
+                ParticipantAccount::LEN,
+                program_id,
+                system_program,
+                &rent,
                 &[
-                    survey_token_account.clone(),
-                    participant_token_account.clone(),
-                    survey_account.clone(),
-                    token_program.clone(),
+                    PARTICIPANT_SEED,
+                    survey_account.key.as_ref(),
+                    participant.key.as_ref(),
+                    &[participant_bump],
                 ],
             )?;
+            init_account(
+                participant_account,
+                &ParticipantAccount::new(*survey_account.key, *participant.key, participant_bump),
+            )?;
+        }
+        let mut participant_data =
+            load_participant_mut(program_id, survey_account.key, participant_account)?;
+        
+        if participant_data.survey != *survey_account.key
+            || participant_data.participant != *participant.key
+        {
+            return Err(SurveyError::ParticipantMismatch.into());
+        }
+        
+        if is_lottery
+            && !participant_data
+                .lottery_entry()
+                .is_some_and(|entry| survey.is_lottery_winner(entry))
+        {
+            return Err(SurveyError::NotAWinner.into());
+        }
+        
+        // Only the first claim takes a slot and fixes the tier; later ones pay
+        // out the remaining legs at that tier
+        let first_claim = !participant_data.has_claimed();
+        if !first_claim && participant_data.reward_tier != reward_tier {
+            return Err(SurveyError::InvalidRewardTier.into());
+        }
+        
+        // Responses close at `ends_at`, so no one new can claim after it;
+        // participants who already claimed can collect their remaining legs
+        // until the claim deadline. Lottery winners are only known after
+        // `ends_at` and claim for the first time then.
+        if first_claim && !is_lottery && clock.unix_timestamp > survey.ends_at {
+            return Err(SurveyError::SurveyEnded.into());
+        }
+        
+        // Early birds keep the share of the reward they earned on their first claim
+        if first_claim {
+            participant_data.reward_bps = survey
+                .reward_curve
+                .reward_bps(
+                    survey.current_participants,
+                    clock.unix_timestamp.saturating_sub(survey.starts_at),
+                )
+                .into();
+        }
+        let reward_bps = u16::from(participant_data.reward_bps);
+        
+        // The referrer is fixed on the first claim like the tier, and must be a
+        // participant who has claimed. A referrer at the cap is not credited,
+        // but the claim itself still pays out.
+        if let Some(referrer) = referrer.filter(|_| first_claim) {
+            if referrer == *participant.key {
+                return Err(SurveyError::SelfReferral.into());
+            }
             
-            participant_data.has_claimed_token = true;
+            let referrer_account = next_account_info(account_info_iter)?;
+            if referrer_account.data_is_empty() {
+                return Err(SurveyError::ReferrerNotParticipant.into());
+            }
+            let mut referrer_data =
+                load_participant_mut(program_id, survey_account.key, referrer_account)?;
+            if referrer_data.participant != referrer || !referrer_data.has_claimed() {
+                return Err(SurveyError::ReferrerNotParticipant.into());
+            }
+            
+            let referrals = u32::from(referrer_data.referrals);
+            if survey.record_referral(referrals)? {
+                referrer_data.referrals = (referrals + 1).into();
+                participant_data.referrer = Some(referrer).try_into()?;
+            }
         }
         
-        // Update participant account
-        let clock = Clock::get()?;
-        participant_data.claimed_at = Some(clock.unix_timestamp);
-        participant_data.serialize(&mut &mut participant_account.data.borrow_mut()[..])?;
+        let (sol_reward_amount, token_reward_amount) =
+            survey.tier_rewards(reward_tier).ok_or(SurveyError::InvalidRewardTier)?;
+        let sol_reward_amount = apply_reward_bps(sol_reward_amount, reward_bps);
+        let token_reward_amount = apply_reward_bps(token_reward_amount, reward_bps);
+        
+        let claim_sol = reward.includes_sol() && !bool::from(participant_data.has_claimed_sol);
+        let claim_token =
+            reward.includes_token() && !bool::from(participant_data.has_claimed_token);
+        if !claim_sol && !claim_token {
+            return Err(SurveyError::AlreadyClaimed.into());
+        }
+        
+        survey.record_claim(reward_tier, first_claim, claim_sol, claim_token)?;
+        
+        let payout = RewardPayoutAccounts {
+            survey_account,
+            recipient: participant,
+            recipient_token_account: participant_token_account,
+            token_pool: survey_token_account,
+            token_mint,
+            token_program,
+            vault_authority,
+            system_program,
+            associated_token_program,
+        };
+        
+        if claim_token && token_reward_amount > 0 {
+            let paid =
+                pay_reward_tokens(program_id, &survey, &payout, token_reward_amount, clock.epoch)?;
+            survey.record_payout(0, paid)?;
+        }
+        
+        if claim_token {
+            participant_data.has_claimed_token = true.into();
+        }
+        
+        // SOL goes after the token CPIs since direct lamport edits must
+        // balance across each invoke
+        if claim_sol && sol_reward_amount > 0 {
+            pay_reward_sol(&payout, sol_reward_amount, &rent)?;
+            survey.record_payout(sol_reward_amount, 0)?;
+        }
+        
+        if claim_sol {
+            participant_data.has_claimed_sol = true.into();
+        }
         
-        // Update survey participant count
-        survey.current_participants += 1;
-        survey.serialize(&mut &mut survey_account.data.borrow_mut()[..])?;
+        if first_claim {
+            participant_data.response_hash = response_hash;
+            participant_data.claimed_at = clock.unix_timestamp;
+            participant_data.reward_tier = reward_tier;
+        }
         
         msg!("Rewards claimed for survey: {}", survey_id);
         Ok(())
@@ -158,92 +460,1744 @@ impl Processor {
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         
-        let owner = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
         let survey_account = next_account_info(account_info_iter)?;
         let participant_account = next_account_info(account_info_iter)?;
-        let participant_nft_account = next_account_info(account_info_iter)?;
+        let participant_wallet = next_account_info(account_info_iter)?;
         let nft_mint = next_account_info(account_info_iter)?;
+        let participant_nft_account = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
         let token_program = next_account_info(account_info_iter)?;
+        let associated_token_program = next_account_info(account_info_iter)?;
         let system_program = next_account_info(account_info_iter)?;
         let rent_sysvar = next_account_info(account_info_iter)?;
         
-        if !owner.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        check_signer(authority)?;
+        check_writable(authority)?;
+        check_writable(nft_mint)?;
+        check_writable(participant_nft_account)?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+        check_program(system_program, &system_program::id())?;
+        check_sysvar(rent_sysvar, &sysvar::rent::id())?;
+        
+        let survey = load_survey(program_id, survey_account)?;
+        
+        if !survey.is_owner_or_operator(authority.key) {
+            return Err(SurveyError::Unauthorized.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        let clock = Clock::get()?;
+        survey.check_claim_window(clock.unix_timestamp)?;
+        
+        let mut participant_data =
+            load_participant_mut(program_id, survey_account.key, participant_account)?;
+        
+        if participant_data.participant != *participant_wallet.key {
+            return Err(SurveyError::ParticipantMismatch.into());
+        }
+        
+        if bool::from(participant_data.has_received_nft) {
+            return Err(SurveyError::AlreadyClaimed.into());
+        }
+        
+        if vault_authority_address(program_id, survey_account.key, survey.vault_bump)?
+            != *vault_authority.key
+        {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        let (expected_mint, mint_bump) =
+            find_nft_mint_address(program_id, survey_account.key, participant_wallet.key);
+        if expected_mint != *nft_mint.key {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        let mint_seeds: &[&[u8]] = &[
+            NFT_MINT_SEED,
+            survey_account.key.as_ref(),
+            participant_wallet.key.as_ref(),
+            &[mint_bump],
+        ];
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, survey_account.key.as_ref(), &[survey.vault_bump]];
+        let nft_accounts = NftAccounts {
+            payer: authority,
+            mint: nft_mint,
+            holder: participant_wallet,
+            holder_token_account: participant_nft_account,
+            vault_authority,
+            token_program,
+            associated_token_program,
+            system_program,
+            rent_sysvar,
+        };
+        
+        if bool::from(survey.soulbound_nfts) {
+            check_program(token_program, &spl_token_2022::id())?;
+            
+            mint_soulbound_badge(
+                &nft_accounts,
+                mint_seeds,
+                vault_seeds,
+                nft_name(&survey_id),
+                survey.nft_uri(),
+            )?;
+        } else {
+            let metadata_account = next_account_info(account_info_iter)?;
+            let master_edition_account = next_account_info(account_info_iter)?;
+            let metadata_program = next_account_info(account_info_iter)?;
+            
+            check_program(token_program, &spl_token::id())?;
+            check_program(metadata_program, &mpl_token_metadata::ID)?;
+            
+            mint_master_edition(
+                &nft_accounts,
+                &MetaplexAccounts {
+                    metadata: metadata_account,
+                    master_edition: master_edition_account,
+                    metadata_program,
+                },
+                mint_seeds,
+                vault_seeds,
+                DataV2 {
+                    name: nft_name(&survey_id),
+                    symbol: NFT_SYMBOL.to_string(),
+                    uri: survey.nft_uri(),
+                    seller_fee_basis_points: 0,
+                    creators: None,
+                    collection: Option::<Pubkey>::from(survey.nft_collection).map(|key| Collection {
+                        verified: false,
+                        key,
+                    }),
+                    uses: None,
+                },
+                None,
+            )?;
+            
+            if let Some(collection) = Option::<Pubkey>::from(survey.nft_collection) {
+                let collection_mint = next_account_info(account_info_iter)?;
+                let collection_metadata = next_account_info(account_info_iter)?;
+                let collection_master_edition = next_account_info(account_info_iter)?;
+                let instructions_sysvar = next_account_info(account_info_iter)?;
+                
+                if *collection_mint.key != collection {
+                    return Err(SurveyError::InvalidAccountAddress.into());
+                }
+                check_sysvar(instructions_sysvar, &sysvar::instructions::id())?;
+                
+                VerifyCollectionV1CpiBuilder::new(metadata_program)
+                    .authority(vault_authority)
+                    .metadata(metadata_account)
+                    .collection_mint(collection_mint)
+                    .collection_metadata(Some(collection_metadata))
+                    .collection_master_edition(Some(collection_master_edition))
+                    .system_program(system_program)
+                    .sysvar_instructions(instructions_sysvar)
+                    .invoke_signed(&[vault_seeds])?;
+            }
         }
         
-        let survey = SurveyAccount::try_from_slice(&survey_account.data.borrow())?;
+        participant_data.has_received_nft = true.into();
+        
+        msg!("NFT distributed for survey: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_close_survey(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        force: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let owner = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        let owner_sol_account = next_account_info(account_info_iter)?;
+        let owner_token_account = next_account_info(account_info_iter)?;
+        let survey_token_account = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let token_mint = next_account_info(account_info_iter)?;
+        
+        check_signer(owner)?;
+        check_writable(owner_sol_account)?;
+        check_writable(owner_token_account)?;
+        
+        // Copy the survey out since its data is wiped at the end
+        check_writable(survey_account)?;
+        let survey = *load_survey(program_id, survey_account)?;
         
         if survey.owner != *owner.key {
             return Err(SurveyError::InvalidOwner.into());
         }
         
-        let mut participant_data = ParticipantAccount::try_from_slice(&participant_account.data.borrow())?;
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
         
-        if participant_data.has_received_nft {
-            return Err(SurveyError::AlreadyClaimed.into());
+        let clock = Clock::get()?;
+        if !force
+            && clock.unix_timestamp <= survey.claim_deadline
+            && !survey.is_settled()
+        {
+            return Err(SurveyError::UnclaimedRewardsOutstanding.into());
         }
         
-        // Mint NFT to participant
-        let mint_to_ix = mint_to(
-            &spl_token::id(),
-            nft_mint.key,
-            participant_nft_account.key,
-            owner.key,
-            &[],
-            1,
+        if vault_authority_address(program_id, survey_account.key, survey.vault_bump)?
+            != *vault_authority.key
+        {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        check_reward_token_accounts(
+            &survey,
+            vault_authority,
+            survey_token_account,
+            token_mint,
+            token_program,
         )?;
         
-        invoke(
-            &mint_to_ix,
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, survey_account.key.as_ref(), &[survey.vault_bump]];
+        
+        // Sweep remaining reward tokens back to the owner
+        let pool_amount = token_account_amount(survey_token_account)?;
+        if pool_amount > 0 {
+            invoke_signed(
+                &transfer_checked(
+                    token_program.key,
+                    survey_token_account.key,
+                    token_mint.key,
+                    owner_token_account.key,
+                    vault_authority.key,
+                    &[],
+                    pool_amount,
+                    survey.token_decimals,
+                )?,
+                &[
+                    survey_token_account.clone(),
+                    token_mint.clone(),
+                    owner_token_account.clone(),
+                    vault_authority.clone(),
+                    token_program.clone(),
+                ],
+                &[vault_seeds],
+            )?;
+        }
+        
+        // Fees withheld from deposits must move to the mint before the pool can close
+        if withheld_transfer_fees(survey_token_account)? > 0 {
+            check_writable(token_mint)?;
+            invoke(
+                &harvest_withheld_tokens_to_mint(
+                    token_program.key,
+                    token_mint.key,
+                    &[survey_token_account.key],
+                )?,
+                &[
+                    token_mint.clone(),
+                    survey_token_account.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        }
+        
+        invoke_signed(
+            &close_account(
+                token_program.key,
+                survey_token_account.key,
+                owner_sol_account.key,
+                vault_authority.key,
+                &[],
+            )?,
             &[
-                nft_mint.clone(),
-                participant_nft_account.clone(),
-                owner.clone(),
+                survey_token_account.clone(),
+                owner_sol_account.clone(),
+                vault_authority.clone(),
                 token_program.clone(),
             ],
+            &[vault_seeds],
         )?;
         
-        participant_data.has_received_nft = true;
-        participant_data.serialize(&mut &mut participant_account.data.borrow_mut()[..])?;
+        // Shrink the account to a tombstone that keeps the survey ID from being
+        // reused over this campaign's participant records
+        survey_account.data.borrow_mut().fill(0);
+        survey_account.realloc(DISCRIMINATOR_LEN, false)?;
+        survey_account
+            .data
+            .borrow_mut()
+            .copy_from_slice(&CLOSED_SURVEY_DISCRIMINATOR);
         
-        msg!("NFT distributed for survey: {}", survey_id);
+        // Return all SOL beyond the tombstone's rent to the owner
+        let tombstone_rent = Rent::get()?.minimum_balance(DISCRIMINATOR_LEN);
+        let remaining_lamports = survey_account.lamports().saturating_sub(tombstone_rent);
+        **survey_account.lamports.borrow_mut() -= remaining_lamports;
+        **owner_sol_account.lamports.borrow_mut() = owner_sol_account
+            .lamports()
+            .checked_add(remaining_lamports)
+            .ok_or(SurveyError::Overflow)?;
+        
+        msg!("Survey closed: {}", survey_id);
         Ok(())
     }
     
-    pub fn process_close_survey(
+    pub fn process_fund_survey(
         accounts: &[AccountInfo],
         program_id: &Pubkey,
         survey_id: String,
+        sol_amount: u64,
+        token_amount: u64,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         
-        let owner = next_account_info(account_info_iter)?;
+        let funder = next_account_info(account_info_iter)?;
         let survey_account = next_account_info(account_info_iter)?;
-        let owner_sol_account = next_account_info(account_info_iter)?;
+        let funder_token_account = next_account_info(account_info_iter)?;
+        let survey_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let token_mint = next_account_info(account_info_iter)?;
         
-        if !owner.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        check_signer(funder)?;
+        check_writable(funder)?;
+        
+        if sol_amount == 0 && token_amount == 0 {
+            return Err(SurveyError::InvalidRewardAmount.into());
         }
         
-        let mut survey = SurveyAccount::try_from_slice(&survey_account.data.borrow())?;
+        let mut survey = load_survey_mut(program_id, survey_account)?;
         
-        if survey.owner != *owner.key {
-            return Err(SurveyError::InvalidOwner.into());
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if !bool::from(survey.is_active) {
+            return Err(SurveyError::SurveyClosed.into());
+        }
+        
+        if sol_amount > 0 {
+            check_program(system_program, &system_program::id())?;
+            
+            // The transfer CPI needs the survey account, so release the in-place view around it
+            drop(survey);
+            invoke(
+                &system_instruction::transfer(funder.key, survey_account.key, sol_amount),
+                &[funder.clone(), survey_account.clone(), system_program.clone()],
+            )?;
+            survey = load_survey_mut(program_id, survey_account)?;
+            
+            survey.total_sol_deposited = survey
+                .total_sol_deposited
+                .checked_add(sol_amount)
+                .ok_or(SurveyError::Overflow)?;
+        }
+        
+        if token_amount > 0 {
+            let vault_authority =
+                vault_authority_address(program_id, survey_account.key, survey.vault_bump)?;
+            if get_associated_token_address_with_program_id(
+                &vault_authority,
+                &survey.token_mint,
+                &survey.token_program,
+            ) != *survey_token_account.key
+            {
+                return Err(SurveyError::InvalidTokenPool.into());
+            }
+            
+            check_program(token_program, &survey.token_program)?;
+            check_writable(survey_token_account)?;
+            check_writable(funder_token_account)?;
+            
+            if *token_mint.key != survey.token_mint {
+                return Err(SurveyError::InvalidAccountAddress.into());
+            }
+            
+            let pool_before = token_account_amount(survey_token_account)?;
+            
+            invoke(
+                &transfer_checked(
+                    token_program.key,
+                    funder_token_account.key,
+                    token_mint.key,
+                    survey_token_account.key,
+                    funder.key,
+                    &[],
+                    token_amount,
+                    survey.token_decimals,
+                )?,
+                &[
+                    funder_token_account.clone(),
+                    token_mint.clone(),
+                    survey_token_account.clone(),
+                    funder.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+            
+            // Record what actually arrived, net of any transfer fee
+            let received = token_account_amount(survey_token_account)?.saturating_sub(pool_before);
+            survey.total_tokens_deposited = survey
+                .total_tokens_deposited
+                .checked_add(received)
+                .ok_or(SurveyError::Overflow)?;
+        }
+        
+        msg!("Survey funded: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_pause_survey(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+    ) -> ProgramResult {
+        Self::set_survey_paused(accounts, program_id, survey_id, true)
+    }
+    
+    pub fn process_resume_survey(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+    ) -> ProgramResult {
+        Self::set_survey_paused(accounts, program_id, survey_id, false)
+    }
+    
+    fn set_survey_paused(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        paused: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let authority = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        
+        check_signer(authority)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if !survey.is_owner_or_operator(authority.key) {
+            return Err(SurveyError::Unauthorized.into());
         }
         
-        if survey.survey_id != survey_id {
+        if !survey.matches_survey_id(&survey_id) {
             return Err(SurveyError::SurveyNotFound.into());
         }
         
-        survey.is_active = false;
-        survey.serialize(&mut &mut survey_account.data.borrow_mut()[..])?;
+        if !bool::from(survey.is_active) {
+            return Err(SurveyError::SurveyClosed.into());
+        }
+        
+        if bool::from(survey.is_paused) == paused {
+            return Err(if paused {
+                SurveyError::SurveyPaused
+            } else {
+                SurveyError::SurveyNotPaused
+            }
+            .into());
+        }
         
-        // Return remaining SOL to owner
-        let remaining_lamports = survey_account.lamports();
-        **survey_account.lamports.borrow_mut() = 0;
-        **owner_sol_account.lamports.borrow_mut() += remaining_lamports;
+        survey.is_paused = paused.into();
         
-        msg!("Survey closed: {}", survey_id);
+        if paused {
+            msg!("Survey paused: {}", survey_id);
+        } else {
+            msg!("Survey resumed: {}", survey_id);
+        }
         Ok(())
     }
-}
\ No newline at end of file
+    
+    pub fn process_update_survey(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        update: SurveyUpdate,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let owner = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        let token_mint = next_account_info(account_info_iter)?;
+        
+        check_signer(owner)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if survey.owner != *owner.key {
+            return Err(SurveyError::InvalidOwner.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if !bool::from(survey.is_active) {
+            return Err(SurveyError::SurveyClosed.into());
+        }
+        
+        if *token_mint.key != survey.token_mint {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        // Token payouts take any transfer fee out of the pool on top of the reward
+        let epoch = Clock::get()?.epoch;
+        let gross = |amount| gross_transfer_amount(token_mint, amount, epoch).ok();
+        
+        // Coverage is judged from recorded deposits, like the claim checks, so
+        // stray transfers into the escrow do not count
+        let was_funded = survey.is_funded(gross);
+        
+        let unclaimed_rewards = survey.unclaimed_rewards();
+        let referral_bonus = survey.referral_bonus;
+        
+        if let Some(max_participants) = update.max_participants {
+            // The number of lottery winners is settled once the draw starts
+            if max_participants < survey.max_participants || survey.lottery_draw_started() {
+                return Err(SurveyError::InvalidUpdate.into());
+            }
+            survey.max_participants = max_participants;
+        }
+        
+        if let Some(sol_reward_amount) = update.sol_reward_amount {
+            survey.sol_reward_amount = sol_reward_amount;
+        }
+        
+        if let Some(token_reward_amount) = update.token_reward_amount {
+            survey.token_reward_amount = token_reward_amount;
+        }
+        
+        if let Some(ends_at) = update.ends_at {
+            // Lottery entries cannot reopen once the draw starts
+            if ends_at < survey.ends_at || survey.lottery_draw_started() {
+                return Err(SurveyError::InvalidUpdate.into());
+            }
+            survey.ends_at = ends_at;
+        }
+        
+        if let Some(claim_deadline) = update.claim_deadline {
+            if claim_deadline < survey.claim_deadline {
+                return Err(SurveyError::InvalidUpdate.into());
+            }
+            survey.claim_deadline = claim_deadline;
+        }
+        
+        if survey.ends_at > survey.claim_deadline {
+            return Err(SurveyError::InvalidSchedule.into());
+        }
+        
+        // Like `CreateCollection`, the collection can only be set once
+        if update.nft_collection.is_some() {
+            if Option::<Pubkey>::from(survey.nft_collection).is_some() {
+                return Err(SurveyError::CollectionAlreadySet.into());
+            }
+            survey.nft_collection = update.nft_collection.try_into()?;
+        }
+        
+        if let Some(soulbound_nfts) = update.soulbound_nfts {
+            survey.soulbound_nfts = soulbound_nfts.into();
+        }
+        
+        if let Some(nft_uri) = update.nft_uri {
+            survey.set_nft_uri(&nft_uri)?;
+        }
+        
+        if let Some(reward_tiers) = update.reward_tiers {
+            let reward_tiers: Vec<RewardTier> = reward_tiers
+                .iter()
+                .map(|tier| {
+                    RewardTier::new(
+                        tier.sol_reward_amount,
+                        tier.token_reward_amount,
+                        tier.max_participants,
+                    )
+                })
+                .collect();
+            survey.set_reward_tiers(&reward_tiers)?;
+        }
+        
+        if let Some(curve) = update.reward_curve {
+            survey.reward_curve = RewardCurve::new(
+                curve.kind,
+                curve.basis,
+                curve.period,
+                curve.floor_bps,
+                curve.step_bps,
+            )?;
+        }
+        
+        if let Some(bonus) = update.referral_bonus {
+            survey.referral_bonus =
+                ReferralBonus::new(bonus.sol_amount, bonus.token_amount, bonus.max_per_referrer);
+        }
+        
+        // Legs are paid at the amounts current when they are claimed, so a leg
+        // a participant holding a slot has yet to claim cannot be lowered
+        for (tier, (sol_owed, tokens_owed)) in unclaimed_rewards.into_iter().enumerate() {
+            let (sol_reward_amount, token_reward_amount) =
+                survey.tier_rewards(tier as u8).unwrap_or_default();
+            if sol_reward_amount < sol_owed || token_reward_amount < tokens_owed {
+                return Err(SurveyError::InvalidUpdate.into());
+            }
+        }
+        
+        // Likewise for referral bonuses recorded but not paid yet
+        if survey.referrals > survey.referral_bonuses_paid
+            && (survey.referral_bonus.sol_amount < referral_bonus.sol_amount
+                || survey.referral_bonus.token_amount < referral_bonus.token_amount)
+        {
+            return Err(SurveyError::InvalidUpdate.into());
+        }
+        
+        if survey.outstanding_sol().is_none() || survey.outstanding_tokens(gross).is_none() {
+            return Err(SurveyError::InvalidRewardAmount.into());
+        }
+        
+        // A campaign that was fully funded must stay fully funded
+        if was_funded && !survey.is_funded(gross) {
+            return Err(SurveyError::UnderfundedUpdate.into());
+        }
+        
+        msg!("Survey updated: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_propose_owner(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        new_owner: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let owner = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        
+        check_signer(owner)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if survey.owner != *owner.key {
+            return Err(SurveyError::InvalidOwner.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        survey.pending_owner = Some(new_owner).try_into()?;
+        
+        msg!("Ownership of survey {} proposed to {}", survey_id, new_owner);
+        Ok(())
+    }
+    
+    pub fn process_accept_owner(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let new_owner = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        
+        check_signer(new_owner)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if Option::<Pubkey>::from(survey.pending_owner) != Some(*new_owner.key) {
+            return Err(SurveyError::NotPendingOwner.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        survey.owner = *new_owner.key;
+        survey.pending_owner = OptionalNonZeroPubkey::default();
+        
+        msg!("Ownership of survey {} accepted by {}", survey_id, new_owner.key);
+        Ok(())
+    }
+    
+    pub fn process_set_operators(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        operators: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let owner = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        
+        check_signer(owner)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if survey.owner != *owner.key {
+            return Err(SurveyError::InvalidOwner.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        survey.set_operators(&operators)?;
+        
+        msg!("Operators set for survey: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_create_collection(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let owner = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        let collection_mint = next_account_info(account_info_iter)?;
+        let collection_token_account = next_account_info(account_info_iter)?;
+        let metadata_account = next_account_info(account_info_iter)?;
+        let master_edition_account = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let associated_token_program = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let rent_sysvar = next_account_info(account_info_iter)?;
+        let metadata_program = next_account_info(account_info_iter)?;
+        
+        check_signer(owner)?;
+        check_writable(owner)?;
+        check_writable(collection_mint)?;
+        check_writable(collection_token_account)?;
+        check_program(token_program, &spl_token::id())?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+        check_program(system_program, &system_program::id())?;
+        check_sysvar(rent_sysvar, &sysvar::rent::id())?;
+        check_program(metadata_program, &mpl_token_metadata::ID)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if survey.owner != *owner.key {
+            return Err(SurveyError::InvalidOwner.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if Option::<Pubkey>::from(survey.nft_collection).is_some() {
+            return Err(SurveyError::CollectionAlreadySet.into());
+        }
+        
+        if vault_authority_address(program_id, survey_account.key, survey.vault_bump)?
+            != *vault_authority.key
+        {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        let (expected_mint, mint_bump) =
+            find_collection_mint_address(program_id, survey_account.key);
+        if expected_mint != *collection_mint.key {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        let mint_seeds: &[&[u8]] = &[COLLECTION_SEED, survey_account.key.as_ref(), &[mint_bump]];
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, survey_account.key.as_ref(), &[survey.vault_bump]];
+        
+        // The vault authority holds the collection NFT and remains its update
+        // authority, so it can verify participation NFTs into it
+        mint_master_edition(
+            &NftAccounts {
+                payer: owner,
+                mint: collection_mint,
+                holder: vault_authority,
+                holder_token_account: collection_token_account,
+                vault_authority,
+                token_program,
+                associated_token_program,
+                system_program,
+                rent_sysvar,
+            },
+            &MetaplexAccounts {
+                metadata: metadata_account,
+                master_edition: master_edition_account,
+                metadata_program,
+            },
+            mint_seeds,
+            vault_seeds,
+            DataV2 {
+                name: nft_name(&survey_id),
+                symbol: NFT_SYMBOL.to_string(),
+                uri: survey.nft_uri(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            Some(CollectionDetails::V1 { size: 0 }),
+        )?;
+        
+        survey.nft_collection = Some(*collection_mint.key).try_into()?;
+        
+        msg!("Collection created for survey: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_create_nft_tree(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let owner = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        let merkle_tree = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
+        let compression_program = next_account_info(account_info_iter)?;
+        let noop_program = next_account_info(account_info_iter)?;
+        
+        check_signer(owner)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if survey.owner != *owner.key {
+            return Err(SurveyError::InvalidOwner.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if Option::<Pubkey>::from(survey.nft_tree).is_some() {
+            return Err(SurveyError::NftTreeAlreadySet.into());
+        }
+        
+        check_writable(merkle_tree)?;
+        check_account_owner(merkle_tree, &ACCOUNT_COMPRESSION_PROGRAM_ID)?;
+        
+        if vault_authority_address(program_id, survey_account.key, survey.vault_bump)?
+            != *vault_authority.key
+        {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        check_program(compression_program, &ACCOUNT_COMPRESSION_PROGRAM_ID)?;
+        check_program(noop_program, &NOOP_PROGRAM_ID)?;
+        
+        invoke_signed(
+            &init_empty_merkle_tree(
+                merkle_tree.key,
+                vault_authority.key,
+                max_depth,
+                max_buffer_size,
+            ),
+            &[
+                merkle_tree.clone(),
+                vault_authority.clone(),
+                noop_program.clone(),
+                compression_program.clone(),
+            ],
+            &[&[VAULT_SEED, survey_account.key.as_ref(), &[survey.vault_bump]]],
+        )?;
+        
+        survey.nft_tree = Some(*merkle_tree.key).try_into()?;
+        survey.nft_tree_leaf_count = 0;
+        
+        msg!("Compressed NFT tree created for survey: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_distribute_compressed_nft(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let authority = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        let participant_account = next_account_info(account_info_iter)?;
+        let merkle_tree = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
+        let compression_program = next_account_info(account_info_iter)?;
+        let noop_program = next_account_info(account_info_iter)?;
+        
+        check_signer(authority)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if !survey.is_owner_or_operator(authority.key) {
+            return Err(SurveyError::Unauthorized.into());
+        }
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        let clock = Clock::get()?;
+        survey.check_claim_window(clock.unix_timestamp)?;
+        
+        match Option::<Pubkey>::from(survey.nft_tree) {
+            None => return Err(SurveyError::MissingNftTree.into()),
+            Some(tree) if tree != *merkle_tree.key => {
+                return Err(SurveyError::InvalidAccountAddress.into())
+            }
+            Some(_) => {}
+        }
+        check_writable(merkle_tree)?;
+        
+        let mut participant_data =
+            load_participant_mut(program_id, survey_account.key, participant_account)?;
+        
+        if bool::from(participant_data.has_received_nft) {
+            return Err(SurveyError::AlreadyClaimed.into());
+        }
+        
+        if vault_authority_address(program_id, survey_account.key, survey.vault_bump)?
+            != *vault_authority.key
+        {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        check_program(compression_program, &ACCOUNT_COMPRESSION_PROGRAM_ID)?;
+        check_program(noop_program, &NOOP_PROGRAM_ID)?;
+        
+        let leaf = participation_leaf(
+            survey_account.key,
+            &participant_data.participant,
+            &participant_data.response_hash,
+        );
+        
+        invoke_signed(
+            &append(merkle_tree.key, vault_authority.key, leaf),
+            &[
+                merkle_tree.clone(),
+                vault_authority.clone(),
+                noop_program.clone(),
+                compression_program.clone(),
+            ],
+            &[&[VAULT_SEED, survey_account.key.as_ref(), &[survey.vault_bump]]],
+        )?;
+        
+        // Only the vault authority can append, so the count is the next leaf's index
+        participant_data.set_nft_leaf_index(survey.nft_tree_leaf_count);
+        participant_data.has_received_nft = true.into();
+        
+        survey.nft_tree_leaf_count = survey
+            .nft_tree_leaf_count
+            .checked_add(1)
+            .ok_or(SurveyError::Overflow)?;
+        
+        msg!("Compressed NFT distributed for survey: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_enter_lottery(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        response_hash: [u8; 32],
+        expiry: UnixTimestamp,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let participant = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        let participant_account = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let verifier_or_instructions = next_account_info(account_info_iter)?;
+        
+        check_signer(participant)?;
+        check_writable(participant)?;
+        check_writable(participant_account)?;
+        check_program(system_program, &system_program::id())?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if !bool::from(survey.is_lottery) {
+            return Err(SurveyError::NotLottery.into());
+        }
+        
+        if !bool::from(survey.is_active) {
+            return Err(SurveyError::SurveyClosed.into());
+        }
+        
+        if bool::from(survey.is_paused) {
+            return Err(SurveyError::SurveyPaused.into());
+        }
+        
+        // Entries are taken while responses are collected
+        let clock = Clock::get()?;
+        if clock.unix_timestamp < survey.starts_at {
+            return Err(SurveyError::SurveyNotStarted.into());
+        }
+        
+        if clock.unix_timestamp > survey.ends_at {
+            return Err(SurveyError::SurveyExpired.into());
+        }
+        
+        check_verifier_authorization(
+            &survey.verifier,
+            verifier_or_instructions,
+            &attestation_message(survey_account.key, participant.key, &response_hash, expiry, 0),
+            expiry,
+            clock.unix_timestamp,
+        )?;
+        
+        let (participant_address, participant_bump) =
+            find_participant_address(program_id, survey_account.key, participant.key);
+        if participant_address != *participant_account.key {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        
+        if !participant_account.data_is_empty() {
+            return Err(SurveyError::AlreadyEntered.into());
+        }
+        
+        create_pda_account(
+            participant,
+            participant_account,
+            ParticipantAccount::LEN,
+            program_id,
+            system_program,
+            &Rent::get()?,
+            &[
+                PARTICIPANT_SEED,
+                survey_account.key.as_ref(),
+                participant.key.as_ref(),
+                &[participant_bump],
+            ],
+        )?;
+        
+        let mut entry =
+            ParticipantAccount::new(*survey_account.key, *participant.key, participant_bump);
+        entry.set_lottery_entry(survey.lottery_entries);
+        entry.response_hash = response_hash;
+        init_account(participant_account, &entry)?;
+        
+        survey.lottery_entries = survey
+            .lottery_entries
+            .checked_add(1)
+            .ok_or(SurveyError::Overflow)?;
+        
+        msg!("Lottery entry registered for survey: {}", survey_id);
+        Ok(())
+    }
+    
+    pub fn process_start_lottery_draw(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+        seed: Option<[u8; 32]>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let survey_account = next_account_info(account_info_iter)?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if !bool::from(survey.is_lottery) {
+            return Err(SurveyError::NotLottery.into());
+        }
+        
+        if !bool::from(survey.is_active) {
+            return Err(SurveyError::SurveyClosed.into());
+        }
+        
+        if survey.lottery_draw_started() {
+            return Err(SurveyError::DrawAlreadyStarted.into());
+        }
+        
+        let clock = Clock::get()?;
+        if clock.unix_timestamp <= survey.ends_at {
+            return Err(SurveyError::LotteryStillOpen.into());
+        }
+        
+        // Without the owner's seed the draw falls back to the commitment, but
+        // only once the owner has had the whole window to reveal it
+        survey.lottery_seed = match seed {
+            Some(seed) if seed_commitment(&seed) == survey.lottery_commitment => seed,
+            Some(_) => return Err(SurveyError::InvalidSeedReveal.into()),
+            None if clock.unix_timestamp
+                > survey.ends_at.saturating_add(LOTTERY_REVEAL_WINDOW) =>
+            {
+                survey.lottery_commitment
+            }
+            None => return Err(SurveyError::RevealWindowOpen.into()),
+        };
+        survey.lottery_draw_slot = clock.slot + LOTTERY_DRAW_DELAY_SLOTS;
+        
+        msg!(
+            "Lottery draw started for survey {} at slot {}",
+            survey_id,
+            survey.lottery_draw_slot
+        );
+        Ok(())
+    }
+    
+    pub fn process_draw_winners(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let survey_account = next_account_info(account_info_iter)?;
+        let slot_hashes = next_account_info(account_info_iter)?;
+        
+        check_sysvar(slot_hashes, &sysvar::slot_hashes::id())?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if !bool::from(survey.is_lottery) {
+            return Err(SurveyError::NotLottery.into());
+        }
+        
+        if !bool::from(survey.is_active) {
+            return Err(SurveyError::SurveyClosed.into());
+        }
+        
+        if bool::from(survey.winners_drawn) {
+            return Err(SurveyError::WinnersAlreadyDrawn.into());
+        }
+        
+        if !survey.lottery_draw_started() {
+            return Err(SurveyError::DrawNotStarted.into());
+        }
+        
+        // The draw slot is final: once its hash has aged out, the draw
+        // completes from the seed alone rather than from a later slot, so
+        // holding back the draw never buys a different outcome than that one
+        match draw_slot_hash(slot_hashes, survey.lottery_draw_slot)? {
+            Some(slot_hash) => survey.lottery_seed = draw_seed(&survey.lottery_seed, &slot_hash),
+            None => msg!(
+                "Lottery draw slot aged out for survey {}, drawing from the seed",
+                survey_id
+            ),
+        }
+        survey.winners_drawn = true.into();
+        
+        msg!(
+            "Winners drawn for survey {} from {} entries",
+            survey_id,
+            survey.lottery_entries
+        );
+        Ok(())
+    }
+    
+    pub fn process_claim_referral_bonus(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        survey_id: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        
+        let referrer = next_account_info(account_info_iter)?;
+        let survey_account = next_account_info(account_info_iter)?;
+        let referrer_account = next_account_info(account_info_iter)?;
+        let referred_account = next_account_info(account_info_iter)?;
+        let referrer_token_account = next_account_info(account_info_iter)?;
+        let survey_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let vault_authority = next_account_info(account_info_iter)?;
+        let token_mint = next_account_info(account_info_iter)?;
+        let associated_token_program = next_account_info(account_info_iter)?;
+        
+        check_signer(referrer)?;
+        check_writable(referrer)?;
+        check_program(system_program, &system_program::id())?;
+        
+        let mut survey = load_survey_mut(program_id, survey_account)?;
+        
+        if !survey.matches_survey_id(&survey_id) {
+            return Err(SurveyError::SurveyNotFound.into());
+        }
+        
+        if !bool::from(survey.is_active) {
+            return Err(SurveyError::SurveyClosed.into());
+        }
+        
+        if bool::from(survey.is_paused) {
+            return Err(SurveyError::SurveyPaused.into());
+        }
+        
+        let clock = Clock::get()?;
+        survey.check_claim_window(clock.unix_timestamp)?;
+        
+        if *token_mint.key != survey.token_mint {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+        if !survey.is_funded(|amount| gross_transfer_amount(token_mint, amount, clock.epoch).ok()) {
+            return Err(SurveyError::SurveyNotFunded.into());
+        }
+        
+        // Only participants of the survey earn referral bonuses
+        let mut referrer_data =
+            load_participant_mut(program_id, survey_account.key, referrer_account)?;
+        if referrer_data.participant != *referrer.key {
+            return Err(SurveyError::ParticipantMismatch.into());
+        }
+        
+        let mut referred_data =
+            load_participant_mut(program_id, survey_account.key, referred_account)?;
+        if Option::<Pubkey>::from(referred_data.referrer) != Some(*referrer.key) {
+            return Err(SurveyError::InvalidReferrer.into());
+        }
+        
+        if bool::from(referred_data.referral_bonus_paid) {
+            return Err(SurveyError::AlreadyClaimed.into());
+        }
+        
+        survey.record_referral_bonus()?;
+        referrer_data.referral_bonuses = u32::from(referrer_data.referral_bonuses)
+            .checked_add(1)
+            .ok_or(SurveyError::Overflow)?
+            .into();
+        referred_data.referral_bonus_paid = true.into();
+        
+        let payout = RewardPayoutAccounts {
+            survey_account,
+            recipient: referrer,
+            recipient_token_account: referrer_token_account,
+            token_pool: survey_token_account,
+            token_mint,
+            token_program,
+            vault_authority,
+            system_program,
+            associated_token_program,
+        };
+        
+        let bonus = survey.referral_bonus;
+        if bonus.token_amount > 0 {
+            let paid =
+                pay_reward_tokens(program_id, &survey, &payout, bonus.token_amount, clock.epoch)?;
+            survey.record_payout(0, paid)?;
+        }
+        
+        if bonus.sol_amount > 0 {
+            pay_reward_sol(&payout, bonus.sol_amount, &Rent::get()?)?;
+            survey.record_payout(bonus.sol_amount, 0)?;
+        }
+        
+        msg!("Referral bonus paid for survey: {}", survey_id);
+        Ok(())
+    }
+}
+
+/// Create a program-owned account at a PDA, tolerating lamports that were
+/// sent to the address before it was initialized.
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    rent: &Rent,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let required_lamports = rent.minimum_balance(space);
+    
+    if new_account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                new_account.key,
+                required_lamports,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+            &[signer_seeds],
+        );
+    }
+    
+    let top_up = required_lamports.saturating_sub(new_account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, new_account.key, top_up),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+        )?;
+    }
+    
+    invoke_signed(
+        &system_instruction::allocate(new_account.key, space as u64),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    
+    invoke_signed(
+        &system_instruction::assign(new_account.key, owner),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+/// Accounts taking part in minting a one-of-one NFT
+struct NftAccounts<'a, 'b> {
+    payer: &'b AccountInfo<'a>,
+    mint: &'b AccountInfo<'a>,
+    holder: &'b AccountInfo<'a>,
+    holder_token_account: &'b AccountInfo<'a>,
+    vault_authority: &'b AccountInfo<'a>,
+    token_program: &'b AccountInfo<'a>,
+    associated_token_program: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    rent_sysvar: &'b AccountInfo<'a>,
+}
+
+/// Token Metadata accounts of an NFT
+struct MetaplexAccounts<'a, 'b> {
+    metadata: &'b AccountInfo<'a>,
+    master_edition: &'b AccountInfo<'a>,
+    metadata_program: &'b AccountInfo<'a>,
+}
+
+/// Create a fresh mint at a PDA, mint a single token to `holder` and attach
+/// Token Metadata and a master edition. The vault authority is the mint and
+/// update authority until the master edition takes over the mint.
+fn mint_master_edition<'a>(
+    accounts: &NftAccounts<'a, '_>,
+    metaplex: &MetaplexAccounts<'a, '_>,
+    mint_seeds: &[&[u8]],
+    vault_seeds: &[&[u8]],
+    data: DataV2,
+    collection_details: Option<CollectionDetails>,
+) -> ProgramResult {
+    let rent = Rent::from_account_info(accounts.rent_sysvar)?;
+    
+    create_pda_account(
+        accounts.payer,
+        accounts.mint,
+        spl_token::state::Mint::LEN,
+        &spl_token::id(),
+        accounts.system_program,
+        &rent,
+        mint_seeds,
+    )?;
+    
+    invoke(
+        &initialize_mint2(
+            &spl_token::id(),
+            accounts.mint.key,
+            accounts.vault_authority.key,
+            Some(accounts.vault_authority.key),
+            0,
+        )?,
+        &[accounts.mint.clone(), accounts.token_program.clone()],
+    )?;
+    
+    invoke(
+        &create_associated_token_account_idempotent(
+            accounts.payer.key,
+            accounts.holder.key,
+            accounts.mint.key,
+            &spl_token::id(),
+        ),
+        &[
+            accounts.payer.clone(),
+            accounts.holder_token_account.clone(),
+            accounts.holder.clone(),
+            accounts.mint.clone(),
+            accounts.system_program.clone(),
+            accounts.token_program.clone(),
+            accounts.associated_token_program.clone(),
+        ],
+    )?;
+    
+    invoke_signed(
+        &mint_to(
+            &spl_token::id(),
+            accounts.mint.key,
+            accounts.holder_token_account.key,
+            accounts.vault_authority.key,
+            &[],
+            1,
+        )?,
+        &[
+            accounts.mint.clone(),
+            accounts.holder_token_account.clone(),
+            accounts.vault_authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        &[vault_seeds],
+    )?;
+    
+    let mut create_metadata = CreateMetadataAccountV3CpiBuilder::new(metaplex.metadata_program);
+    create_metadata
+        .metadata(metaplex.metadata)
+        .mint(accounts.mint)
+        .mint_authority(accounts.vault_authority)
+        .payer(accounts.payer)
+        .update_authority(accounts.vault_authority, true)
+        .system_program(accounts.system_program)
+        .rent(Some(accounts.rent_sysvar))
+        .data(data)
+        .is_mutable(true);
+    if let Some(collection_details) = collection_details {
+        create_metadata.collection_details(collection_details);
+    }
+    create_metadata.invoke_signed(&[vault_seeds])?;
+    
+    // A master edition with no prints caps the supply at one
+    CreateMasterEditionV3CpiBuilder::new(metaplex.metadata_program)
+        .edition(metaplex.master_edition)
+        .mint(accounts.mint)
+        .update_authority(accounts.vault_authority)
+        .mint_authority(accounts.vault_authority)
+        .payer(accounts.payer)
+        .metadata(metaplex.metadata)
+        .token_program(accounts.token_program)
+        .system_program(accounts.system_program)
+        .rent(Some(accounts.rent_sysvar))
+        .max_supply(0)
+        .invoke_signed(&[vault_seeds])
+}
+
+/// Mint a non-transferable Token-2022 badge to `holder`, carrying its name,
+/// symbol and URI in the mint's own token metadata. The mint authority is
+/// removed afterwards so the supply stays at one.
+fn mint_soulbound_badge(
+    accounts: &NftAccounts,
+    mint_seeds: &[&[u8]],
+    vault_seeds: &[&[u8]],
+    name: String,
+    uri: String,
+) -> ProgramResult {
+    let rent = Rent::from_account_info(accounts.rent_sysvar)?;
+    let token_program_id = accounts.token_program.key;
+    
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::NonTransferable,
+        ExtensionType::MetadataPointer,
+    ])?;
+    let metadata_len = TokenMetadata {
+        name: name.clone(),
+        symbol: NFT_SYMBOL.to_string(),
+        uri: uri.clone(),
+        ..Default::default()
+    }
+    .tlv_size_of()?;
+    
+    create_pda_account(
+        accounts.payer,
+        accounts.mint,
+        space,
+        token_program_id,
+        accounts.system_program,
+        &rent,
+        mint_seeds,
+    )?;
+    
+    // Token-2022 grows the mint for the metadata itself but needs the rent up front
+    let top_up = rent
+        .minimum_balance(space + metadata_len)
+        .saturating_sub(accounts.mint.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(accounts.payer.key, accounts.mint.key, top_up),
+            &[
+                accounts.payer.clone(),
+                accounts.mint.clone(),
+                accounts.system_program.clone(),
+            ],
+        )?;
+    }
+    
+    invoke(
+        &initialize_non_transferable_mint(token_program_id, accounts.mint.key)?,
+        &[accounts.mint.clone(), accounts.token_program.clone()],
+    )?;
+    
+    invoke(
+        &metadata_pointer::instruction::initialize(
+            token_program_id,
+            accounts.mint.key,
+            Some(*accounts.vault_authority.key),
+            Some(*accounts.mint.key),
+        )?,
+        &[accounts.mint.clone(), accounts.token_program.clone()],
+    )?;
+    
+    invoke(
+        &spl_token_2022::instruction::initialize_mint2(
+            token_program_id,
+            accounts.mint.key,
+            accounts.vault_authority.key,
+            None,
+            0,
+        )?,
+        &[accounts.mint.clone(), accounts.token_program.clone()],
+    )?;
+    
+    invoke_signed(
+        &initialize_token_metadata(
+            token_program_id,
+            accounts.mint.key,
+            accounts.vault_authority.key,
+            accounts.mint.key,
+            accounts.vault_authority.key,
+            name,
+            NFT_SYMBOL.to_string(),
+            uri,
+        ),
+        &[
+            accounts.mint.clone(),
+            accounts.vault_authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        &[vault_seeds],
+    )?;
+    
+    invoke(
+        &create_associated_token_account_idempotent(
+            accounts.payer.key,
+            accounts.holder.key,
+            accounts.mint.key,
+            token_program_id,
+        ),
+        &[
+            accounts.payer.clone(),
+            accounts.holder_token_account.clone(),
+            accounts.holder.clone(),
+            accounts.mint.clone(),
+            accounts.system_program.clone(),
+            accounts.token_program.clone(),
+            accounts.associated_token_program.clone(),
+        ],
+    )?;
+    
+    invoke_signed(
+        &spl_token_2022::instruction::mint_to(
+            token_program_id,
+            accounts.mint.key,
+            accounts.holder_token_account.key,
+            accounts.vault_authority.key,
+            &[],
+            1,
+        )?,
+        &[
+            accounts.mint.clone(),
+            accounts.holder_token_account.clone(),
+            accounts.vault_authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        &[vault_seeds],
+    )?;
+    
+    invoke_signed(
+        &spl_token_2022::instruction::set_authority(
+            token_program_id,
+            accounts.mint.key,
+            None,
+            AuthorityType::MintTokens,
+            accounts.vault_authority.key,
+            &[],
+        )?,
+        &[
+            accounts.mint.clone(),
+            accounts.vault_authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        &[vault_seeds],
+    )
+}
+
+/// Check that the survey verifier authorized `message`, either by co-signing
+/// or through an ed25519 instruction that has not expired
+fn check_verifier_authorization(
+    verifier: &Pubkey,
+    verifier_or_instructions: &AccountInfo,
+    message: &[u8; ATTESTATION_MESSAGE_LEN],
+    expiry: UnixTimestamp,
+    now: UnixTimestamp,
+) -> ProgramResult {
+    if verifier_or_instructions.key == verifier {
+        if !verifier_or_instructions.is_signer {
+            return Err(SurveyError::MissingAttestation.into());
+        }
+    } else if *verifier_or_instructions.key == sysvar::instructions::id() {
+        if expiry < now {
+            return Err(SurveyError::AttestationExpired.into());
+        }
+        verify_ed25519_attestation(verifier_or_instructions, verifier, message)?;
+    } else {
+        return Err(SurveyError::MissingAttestation.into());
+    }
+    
+    Ok(())
+}
+
+/// Accounts taking part in paying a reward out of a survey's escrow
+struct RewardPayoutAccounts<'a, 'b> {
+    survey_account: &'b AccountInfo<'a>,
+    recipient: &'b AccountInfo<'a>,
+    recipient_token_account: &'b AccountInfo<'a>,
+    token_pool: &'b AccountInfo<'a>,
+    token_mint: &'b AccountInfo<'a>,
+    token_program: &'b AccountInfo<'a>,
+    vault_authority: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    associated_token_program: &'b AccountInfo<'a>,
+}
+
+/// Transfer `amount` reward tokens from the pool to the recipient's associated
+/// token account, signed by the vault authority that owns the pool. Returns
+/// what left the pool, `amount` grossed up for any transfer fee.
+fn pay_reward_tokens(
+    program_id: &Pubkey,
+    survey: &SurveyAccount,
+    accounts: &RewardPayoutAccounts,
+    amount: u64,
+    epoch: Epoch,
+) -> Result<u64, ProgramError> {
+    if vault_authority_address(program_id, accounts.survey_account.key, survey.vault_bump)?
+        != *accounts.vault_authority.key
+    {
+        return Err(SurveyError::InvalidAccountAddress.into());
+    }
+    
+    check_reward_token_accounts(
+        survey,
+        accounts.vault_authority,
+        accounts.token_pool,
+        accounts.token_mint,
+        accounts.token_program,
+    )?;
+    check_writable(accounts.recipient_token_account)?;
+    check_program(accounts.associated_token_program, &spl_associated_token_account::id())?;
+    
+    // New wallets may not hold the reward token yet, so create their ATA on the fly
+    if get_associated_token_address_with_program_id(
+        accounts.recipient.key,
+        &survey.token_mint,
+        &survey.token_program,
+    ) != *accounts.recipient_token_account.key
+    {
+        return Err(SurveyError::InvalidParticipantTokenAccount.into());
+    }
+    
+    invoke(
+        &create_associated_token_account_idempotent(
+            accounts.recipient.key,
+            accounts.recipient.key,
+            accounts.token_mint.key,
+            accounts.token_program.key,
+        ),
+        &[
+            accounts.recipient.clone(),
+            accounts.recipient_token_account.clone(),
+            accounts.recipient.clone(),
+            accounts.token_mint.clone(),
+            accounts.system_program.clone(),
+            accounts.token_program.clone(),
+            accounts.associated_token_program.clone(),
+        ],
+    )?;
+    
+    let (token_account_mint, token_account_owner) =
+        token_account_mint_and_owner(accounts.recipient_token_account)?;
+    if token_account_mint != survey.token_mint || token_account_owner != *accounts.recipient.key {
+        return Err(SurveyError::InvalidParticipantTokenAccount.into());
+    }
+    
+    // Gross up for any transfer fee so the recipient receives the advertised amount
+    let gross_amount = gross_transfer_amount(accounts.token_mint, amount, epoch)?;
+    
+    invoke_signed(
+        &transfer_checked(
+            accounts.token_program.key,
+            accounts.token_pool.key,
+            accounts.token_mint.key,
+            accounts.recipient_token_account.key,
+            accounts.vault_authority.key,
+            &[],
+            gross_amount,
+            survey.token_decimals,
+        )?,
+        &[
+            accounts.token_pool.clone(),
+            accounts.token_mint.clone(),
+            accounts.recipient_token_account.clone(),
+            accounts.vault_authority.clone(),
+            accounts.token_program.clone(),
+        ],
+        &[&[VAULT_SEED, accounts.survey_account.key.as_ref(), &[survey.vault_bump]]],
+    )?;
+    
+    Ok(gross_amount)
+}
+
+/// Transfer `amount` lamports from the survey account to the recipient,
+/// keeping the survey account rent exempt
+fn pay_reward_sol(accounts: &RewardPayoutAccounts, amount: u64, rent: &Rent) -> ProgramResult {
+    let remaining_lamports = accounts
+        .survey_account
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(SurveyError::InsufficientFunds)?;
+    if remaining_lamports < rent.minimum_balance(SurveyAccount::LEN) {
+        return Err(SurveyError::RentExemptionViolation.into());
+    }
+    
+    let recipient_lamports = accounts
+        .recipient
+        .lamports()
+        .checked_add(amount)
+        .ok_or(SurveyError::Overflow)?;
+    
+    **accounts.survey_account.lamports.borrow_mut() = remaining_lamports;
+    **accounts.recipient.lamports.borrow_mut() = recipient_lamports;
+    
+    Ok(())
+}
+
+/// Validate the vault authority, token pool, reward mint and token program
+/// passed to an instruction that moves reward tokens
+fn check_reward_token_accounts(
+    survey: &SurveyAccount,
+    vault_authority: &AccountInfo,
+    token_pool: &AccountInfo,
+    token_mint: &AccountInfo,
+    token_program: &AccountInfo,
+) -> ProgramResult {
+    check_program(token_program, &survey.token_program)?;
+    check_writable(token_pool)?;
+    
+    if *token_mint.key != survey.token_mint {
+        return Err(SurveyError::InvalidAccountAddress.into());
+    }
+    
+    if get_associated_token_address_with_program_id(
+        vault_authority.key,
+        &survey.token_mint,
+        &survey.token_program,
+    ) != *token_pool.key
+    {
+        return Err(SurveyError::InvalidTokenPool.into());
+    }
+    
+    Ok(())
+}
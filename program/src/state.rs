@@ -1,92 +1,756 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
 use solana_program::{
     clock::UnixTimestamp,
     program_error::ProgramError,
-    pubkey::Pubkey,
+    pubkey::{Pubkey, MAX_SEED_LEN},
 };
+use spl_pod::{
+    optional_keys::OptionalNonZeroPubkey,
+    primitives::{PodBool, PodU16, PodU32},
+};
+
+use mpl_token_metadata::{MAX_NAME_LENGTH, MAX_URI_LENGTH};
+
+use crate::{error::SurveyError, lottery::is_winner, pda::hashed_survey_id};
+
+/// Maximum number of delegated operators per survey
+pub const MAX_OPERATORS: usize = 5;
+
+/// Maximum number of reward tiers besides the base reward
+pub const MAX_REWARD_TIERS: usize = 4;
+
+/// A full reward, in basis points
+pub const FULL_REWARD_BPS: u16 = 10_000;
+
+/// Token Metadata symbol of participation NFTs
+pub const NFT_SYMBOL: &str = "SURVEY";
+
+/// Maximum survey ID length; the ID is a seed of the survey address
+pub const MAX_SURVEY_ID_LEN: usize = MAX_SEED_LEN;
+
+/// Maximum length of a survey title used as a hashed survey ID
+pub const MAX_SURVEY_TITLE_LEN: usize = 128;
+
+/// Size of the type tag at the start of every program-owned account.
+///
+/// Each account starts with an 8-byte discriminator, the first 8 bytes of
+/// `sha256("account:<name>")`, followed by a layout version. Changing a layout
+/// means bumping its `VERSION` and teaching the loaders in `validation` to
+/// read the previous one, so older accounts keep loading until they are
+/// rewritten in the current layout. Version 1 was the Borsh encoding, which
+/// cannot be read in place, so the loaders reject it with
+/// `UnsupportedAccountVersion`.
+pub const DISCRIMINATOR_LEN: usize = 8;
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+/// All that remains of a closed survey account, `sha256("account:ClosedSurveyAccount")`.
+/// Keeping the address taken stops the survey ID from being initialized again
+/// on top of the closed campaign's participant records.
+pub const CLOSED_SURVEY_DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [87, 174, 226, 12, 200, 84, 135, 140];
+
+/// Name of participation NFTs, the survey ID cut to the Token Metadata name limit
+pub fn nft_name(survey_id: &str) -> String {
+    let mut end = survey_id.len().min(MAX_NAME_LENGTH);
+    while !survey_id.is_char_boundary(end) {
+        end -= 1;
+    }
+    survey_id[..end].to_string()
+}
+
+/// Survey state, read and written in place. Fields are ordered so the
+/// `repr(C)` layout has no implicit padding.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct SurveyAccount {
-    pub is_initialized: bool,
-    pub survey_id: String,
+    pub discriminator: [u8; DISCRIMINATOR_LEN],
+    pub version: u8,
+    pub bump: u8,
+    pub vault_bump: u8,
+    pub token_decimals: u8,
+    pub is_active: PodBool,
+    pub is_paused: PodBool,
+    pub soulbound_nfts: PodBool,
+    survey_id_len: u8,
+    survey_id: [u8; MAX_SURVEY_ID_LEN],
+    pub creator: Pubkey,
     pub owner: Pubkey,
+    pub pending_owner: OptionalNonZeroPubkey,
+    pub verifier: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_program: Pubkey,
+    pub nft_collection: OptionalNonZeroPubkey,
+    pub nft_tree: OptionalNonZeroPubkey,
+    operators: [Pubkey; MAX_OPERATORS],
     pub sol_reward_amount: u64,
     pub token_reward_amount: u64,
-    pub token_mint: Pubkey,
+    pub total_sol_deposited: u64,
+    pub total_tokens_deposited: u64,
+    /// Lamports paid out in rewards and referral bonuses
+    pub total_sol_paid: u64,
+    /// Reward tokens that left the pool, including transfer fees
+    pub total_tokens_paid: u64,
+    pub created_at: UnixTimestamp,
+    pub starts_at: UnixTimestamp,
+    pub ends_at: UnixTimestamp,
+    pub claim_deadline: UnixTimestamp,
+    pub nft_tree_leaf_count: u64,
+    /// Slot whose hash completes the lottery draw, zero until the draw starts
+    pub lottery_draw_slot: u64,
+    reward_tiers: [RewardTier; MAX_REWARD_TIERS],
+    pub reward_curve: RewardCurve,
+    pub referral_bonus: ReferralBonus,
     pub max_participants: u32,
     pub current_participants: u32,
-    pub created_at: UnixTimestamp,
-    pub is_active: bool,
-    pub nft_collection: Option<Pubkey>,
+    /// Participants that have claimed the SOL reward
+    pub sol_claims: u32,
+    /// Participants that have claimed the token reward
+    pub token_claims: u32,
+    /// Entries registered in a lottery survey
+    pub lottery_entries: u32,
+    /// Participants that named a referrer on their first claim
+    pub referrals: u32,
+    /// Referrals whose referrer has been paid the bonus
+    pub referral_bonuses_paid: u32,
+    operator_count: u8,
+    nft_uri_len: u8,
+    nft_uri: [u8; MAX_URI_LENGTH],
+    /// Whether `survey_id` holds `sha256(title)` rather than the ID itself
+    pub hashed_survey_id: PodBool,
+    reward_tier_count: u8,
+    /// Whether only drawn entries can claim, with `max_participants` winners
+    pub is_lottery: PodBool,
+    pub winners_drawn: PodBool,
+    /// `sha256(seed)` of the seed the owner reveals to draw winners
+    pub lottery_commitment: [u8; 32],
+    /// Seed the draw was started with, then the randomness the winners were
+    /// drawn with
+    pub lottery_seed: [u8; 32],
+    _padding: [u8; 6],
 }
 
 impl SurveyAccount {
-    pub const LEN: usize = 1 + // is_initialized
-        4 + 64 + // survey_id (max 64 chars)
-        32 + // owner
-        8 + // sol_reward_amount
-        8 + // token_reward_amount
-        32 + // token_mint
-        4 + // max_participants
-        4 + // current_participants
-        8 + // created_at
-        1 + // is_active
-        1 + 32; // nft_collection (Option)
+    pub const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [192, 125, 54, 163, 114, 53, 139, 224];
+    pub const VERSION: u8 = 2;
+    
+    pub const LEN: usize = std::mem::size_of::<Self>();
     
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        survey_id: String,
+        survey_id: &[u8],
         owner: Pubkey,
+        verifier: Pubkey,
         sol_reward_amount: u64,
         token_reward_amount: u64,
         token_mint: Pubkey,
+        token_program: Pubkey,
+        token_decimals: u8,
         max_participants: u32,
         created_at: UnixTimestamp,
-    ) -> Self {
-        Self {
-            is_initialized: true,
-            survey_id,
+        starts_at: UnixTimestamp,
+        ends_at: UnixTimestamp,
+        claim_deadline: UnixTimestamp,
+        bump: u8,
+        vault_bump: u8,
+    ) -> Result<Self, ProgramError> {
+        let mut survey = Self {
+            discriminator: Self::DISCRIMINATOR,
+            version: Self::VERSION,
+            creator: owner,
             owner,
+            verifier,
             sol_reward_amount,
             token_reward_amount,
             token_mint,
+            token_program,
+            token_decimals,
             max_participants,
-            current_participants: 0,
             created_at,
-            is_active: true,
-            nft_collection: None,
+            starts_at,
+            ends_at,
+            claim_deadline,
+            is_active: true.into(),
+            bump,
+            vault_bump,
+            ..Zeroable::zeroed()
+        };
+        survey.set_survey_id(survey_id)?;
+        Ok(survey)
+    }
+    
+    /// Survey ID as used in the survey address
+    pub fn survey_id(&self) -> &[u8] {
+        &self.survey_id[..self.survey_id_len as usize]
+    }
+    
+    fn set_survey_id(&mut self, survey_id: &[u8]) -> Result<(), ProgramError> {
+        if survey_id.len() > MAX_SURVEY_ID_LEN {
+            return Err(ProgramError::MaxSeedLengthExceeded);
+        }
+        self.survey_id = [0; MAX_SURVEY_ID_LEN];
+        self.survey_id[..survey_id.len()].copy_from_slice(survey_id);
+        self.survey_id_len = survey_id.len() as u8;
+        Ok(())
+    }
+    
+    /// Whether `survey_id`, as passed to an instruction, names this survey
+    pub fn matches_survey_id(&self, survey_id: &str) -> bool {
+        if bool::from(self.hashed_survey_id) {
+            self.survey_id() == hashed_survey_id(survey_id)
+        } else {
+            self.survey_id() == survey_id.as_bytes()
+        }
+    }
+    
+    /// Metadata URI of participation NFTs
+    pub fn nft_uri(&self) -> String {
+        String::from_utf8_lossy(&self.nft_uri[..self.nft_uri_len as usize]).into_owned()
+    }
+    
+    pub fn set_nft_uri(&mut self, uri: &str) -> Result<(), SurveyError> {
+        if uri.len() > MAX_URI_LENGTH {
+            return Err(SurveyError::InvalidMetadata);
+        }
+        self.nft_uri = [0; MAX_URI_LENGTH];
+        self.nft_uri[..uri.len()].copy_from_slice(uri.as_bytes());
+        self.nft_uri_len = uri.len() as u8;
+        Ok(())
+    }
+    
+    /// Keys allowed to run operational instructions besides the owner
+    pub fn operators(&self) -> &[Pubkey] {
+        &self.operators[..self.operator_count as usize]
+    }
+    
+    pub fn set_operators(&mut self, operators: &[Pubkey]) -> Result<(), SurveyError> {
+        if operators.len() > MAX_OPERATORS {
+            return Err(SurveyError::TooManyOperators);
+        }
+        self.operators = [Pubkey::default(); MAX_OPERATORS];
+        self.operators[..operators.len()].copy_from_slice(operators);
+        self.operator_count = operators.len() as u8;
+        Ok(())
+    }
+    
+    /// Whether `key` may run operational instructions such as pausing and NFT distribution
+    pub fn is_owner_or_operator(&self, key: &Pubkey) -> bool {
+        self.owner == *key || self.operators().contains(key)
+    }
+    
+    /// Ensure `now` falls between the survey start and its claim deadline
+    pub fn check_claim_window(&self, now: UnixTimestamp) -> Result<(), SurveyError> {
+        if now < self.starts_at {
+            return Err(SurveyError::SurveyNotStarted);
+        }
+        
+        if now > self.claim_deadline {
+            return Err(SurveyError::SurveyExpired);
+        }
+        
+        Ok(())
+    }
+    
+    /// Participant slots that have not been claimed yet
+    pub fn remaining_slots(&self) -> u64 {
+        self.max_participants.saturating_sub(self.current_participants) as u64
+    }
+    
+    /// Reward tiers a verifier can attest to besides the base reward, tier 0.
+    /// Tier `n` is `reward_tiers()[n - 1]`.
+    pub fn reward_tiers(&self) -> &[RewardTier] {
+        &self.reward_tiers[..self.reward_tier_count as usize]
+    }
+    
+    /// Replace the reward tiers, keeping the claims recorded against each
+    /// tier index. Tiers that participants have claimed at cannot be removed
+    /// or capped below their participant count.
+    pub fn set_reward_tiers(&mut self, tiers: &[RewardTier]) -> Result<(), SurveyError> {
+        if tiers.len() > MAX_REWARD_TIERS {
+            return Err(SurveyError::TooManyRewardTiers);
+        }
+        
+        let mut updated = [RewardTier::default(); MAX_REWARD_TIERS];
+        for (index, current) in self.reward_tiers.iter().enumerate() {
+            let tier = match tiers.get(index) {
+                Some(tier) => tier,
+                None if current.participants == 0 => continue,
+                None => return Err(SurveyError::InvalidUpdate),
+            };
+            
+            if tier.max_participants < current.participants {
+                return Err(SurveyError::InvalidUpdate);
+            }
+            
+            updated[index] = RewardTier {
+                participants: current.participants,
+                sol_claims: current.sol_claims,
+                token_claims: current.token_claims,
+                ..*tier
+            };
+        }
+        
+        self.reward_tiers = updated;
+        self.reward_tier_count = tiers.len() as u8;
+        Ok(())
+    }
+    
+    /// The base reward as a tier, holding every participant not in another tier
+    fn base_tier(&self) -> RewardTier {
+        let tiers = self.reward_tiers();
+        RewardTier {
+            sol_reward_amount: self.sol_reward_amount,
+            token_reward_amount: self.token_reward_amount,
+            max_participants: self.max_participants,
+            participants: self
+                .current_participants
+                .saturating_sub(tiers.iter().map(|tier| tier.participants).sum()),
+            sol_claims: self
+                .sol_claims
+                .saturating_sub(tiers.iter().map(|tier| tier.sol_claims).sum()),
+            token_claims: self
+                .token_claims
+                .saturating_sub(tiers.iter().map(|tier| tier.token_claims).sum()),
+        }
+    }
+    
+    /// The base tier followed by the reward tiers, and how many of them there are
+    fn all_tiers(&self) -> ([RewardTier; MAX_REWARD_TIERS + 1], usize) {
+        let mut tiers = [self.base_tier(); MAX_REWARD_TIERS + 1];
+        let count = self.reward_tiers().len() + 1;
+        tiers[1..count].copy_from_slice(self.reward_tiers());
+        (tiers, count)
+    }
+    
+    /// SOL and token reward of `tier`, if the survey has it
+    pub fn tier_rewards(&self, tier: u8) -> Option<(u64, u64)> {
+        let tier = match tier {
+            0 => self.base_tier(),
+            n => *self.reward_tiers().get(n as usize - 1)?,
+        };
+        Some((tier.sol_reward_amount, tier.token_reward_amount))
+    }
+    
+    /// Count a claim at `tier`, taking a slot on the participant's first claim
+    pub fn record_claim(
+        &mut self,
+        tier: u8,
+        first_claim: bool,
+        claim_sol: bool,
+        claim_token: bool,
+    ) -> Result<(), SurveyError> {
+        if first_claim && self.remaining_slots() == 0 {
+            return Err(SurveyError::SurveyFull);
+        }
+        
+        let counters = [
+            (first_claim, &mut self.current_participants),
+            (claim_sol, &mut self.sol_claims),
+            (claim_token, &mut self.token_claims),
+        ];
+        for (counted, counter) in counters {
+            if counted {
+                *counter = counter.checked_add(1).ok_or(SurveyError::Overflow)?;
+            }
+        }
+        
+        if tier == 0 {
+            return Ok(());
+        }
+        
+        let tier = self
+            .reward_tiers
+            .get_mut(..self.reward_tier_count as usize)
+            .and_then(|tiers| tiers.get_mut(tier as usize - 1))
+            .ok_or(SurveyError::InvalidRewardTier)?;
+        if first_claim && tier.participants >= tier.max_participants {
+            return Err(SurveyError::RewardTierFull);
+        }
+        
+        let counters = [
+            (first_claim, &mut tier.participants),
+            (claim_sol, &mut tier.sol_claims),
+            (claim_token, &mut tier.token_claims),
+        ];
+        for (counted, counter) in counters {
+            if counted {
+                *counter = counter.checked_add(1).ok_or(SurveyError::Overflow)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// SOL and token reward each tier still owes participants that hold a
+    /// slot, indexed by tier: a leg's amount if any of them have yet to claim
+    /// it, zero otherwise
+    pub fn unclaimed_rewards(&self) -> [(u64, u64); MAX_REWARD_TIERS + 1] {
+        let (tiers, count) = self.all_tiers();
+        let mut unclaimed = [(0, 0); MAX_REWARD_TIERS + 1];
+        for (owed, tier) in unclaimed.iter_mut().zip(&tiers[..count]) {
+            if tier.participants > tier.sol_claims {
+                owed.0 = tier.sol_reward_amount;
+            }
+            if tier.participants > tier.token_claims {
+                owed.1 = tier.token_reward_amount;
+            }
+        }
+        unclaimed
+    }
+    
+    /// Worst case still owed for one reward leg: what participants holding a
+    /// slot have yet to claim, plus every remaining slot taken at the
+    /// best-paying tiers that still have room. `gross` maps each payout to
+    /// what it takes out of the escrow.
+    fn outstanding(
+        &self,
+        amount: impl Fn(&RewardTier) -> u64,
+        claims: impl Fn(&RewardTier) -> u32,
+        gross: &impl Fn(u64) -> Option<u64>,
+    ) -> Option<u64> {
+        let (mut tiers, count) = self.all_tiers();
+        let tiers = &mut tiers[..count];
+        
+        let mut owed: u64 = 0;
+        for tier in tiers.iter() {
+            let unclaimed = tier.participants.saturating_sub(claims(tier)) as u64;
+            owed = owed.checked_add(gross(amount(tier))?.checked_mul(unclaimed)?)?;
+        }
+        
+        tiers.sort_unstable_by_key(|tier| std::cmp::Reverse(amount(tier)));
+        let mut slots = self.remaining_slots();
+        for tier in tiers.iter() {
+            let room = (tier.max_participants.saturating_sub(tier.participants) as u64).min(slots);
+            owed = owed.checked_add(gross(amount(tier))?.checked_mul(room)?)?;
+            slots -= room;
+        }
+        
+        Some(owed)
+    }
+    
+    /// Worst case still owed in referral bonuses of `amount`: the referrals
+    /// not yet paid, plus one for every remaining slot while referrals are
+    /// enabled. Referrals are only recorded within the per-referrer cap, so
+    /// every recorded one is payable.
+    fn outstanding_referrals(
+        &self,
+        amount: u64,
+        gross: &impl Fn(u64) -> Option<u64>,
+    ) -> Option<u64> {
+        let mut payable = self.referrals.saturating_sub(self.referral_bonuses_paid) as u64;
+        if self.referral_bonus.max_per_referrer > 0 {
+            payable = payable.checked_add(self.remaining_slots())?;
+        }
+        gross(amount)?.checked_mul(payable)
+    }
+    
+    /// Lamports still owed if every slot claims the SOL reward and earns its
+    /// referrer a bonus
+    pub fn outstanding_sol(&self) -> Option<u64> {
+        self.outstanding(|tier| tier.sol_reward_amount, |tier| tier.sol_claims, &Some)?
+            .checked_add(self.outstanding_referrals(self.referral_bonus.sol_amount, &Some)?)
+    }
+    
+    /// Tokens still owed if every slot claims the token reward and earns its
+    /// referrer a bonus, with each payout grossed up by `gross` for the
+    /// mint's transfer fee
+    pub fn outstanding_tokens(&self, gross: impl Fn(u64) -> Option<u64>) -> Option<u64> {
+        self.outstanding(|tier| tier.token_reward_amount, |tier| tier.token_claims, &gross)?
+            .checked_add(self.outstanding_referrals(self.referral_bonus.token_amount, &gross)?)
+    }
+    
+    /// Count a referral named on a participant's first claim for a referrer
+    /// credited with `recorded` referrals before. Returns false, counting
+    /// nothing, once the referrer has reached the per-referrer cap.
+    pub fn record_referral(&mut self, recorded: u32) -> Result<bool, SurveyError> {
+        if recorded >= self.referral_bonus.max_per_referrer {
+            return Ok(false);
+        }
+        
+        self.referrals = self.referrals.checked_add(1).ok_or(SurveyError::Overflow)?;
+        Ok(true)
+    }
+    
+    /// Count a referral bonus paid out
+    pub fn record_referral_bonus(&mut self) -> Result<(), SurveyError> {
+        self.referral_bonuses_paid = self
+            .referral_bonuses_paid
+            .checked_add(1)
+            .ok_or(SurveyError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Whether the lottery draw has been started, fixing its seed
+    pub fn lottery_draw_started(&self) -> bool {
+        self.lottery_draw_slot != 0
+    }
+    
+    /// Whether lottery `entry` was drawn as one of the `max_participants` winners
+    pub fn is_lottery_winner(&self, entry: u32) -> bool {
+        bool::from(self.winners_drawn)
+            && is_winner(&self.lottery_seed, entry, self.lottery_entries, self.max_participants)
+    }
+    
+    /// Count a payout of `sol` lamports and `tokens` reward tokens
+    pub fn record_payout(&mut self, sol: u64, tokens: u64) -> Result<(), SurveyError> {
+        self.total_sol_paid = self.total_sol_paid.checked_add(sol).ok_or(SurveyError::Overflow)?;
+        self.total_tokens_paid = self
+            .total_tokens_paid
+            .checked_add(tokens)
+            .ok_or(SurveyError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Whether the deposits not yet paid out cover everything still owed,
+    /// with token payouts grossed up by `gross_tokens`
+    pub fn is_funded(&self, gross_tokens: impl Fn(u64) -> Option<u64>) -> bool {
+        let covers = |deposited: u64, paid: u64, owed: Option<u64>| {
+            matches!(owed, Some(owed) if owed <= deposited.saturating_sub(paid))
+        };
+        covers(self.total_sol_deposited, self.total_sol_paid, self.outstanding_sol())
+            && covers(
+                self.total_tokens_deposited,
+                self.total_tokens_paid,
+                self.outstanding_tokens(gross_tokens),
+            )
+    }
+    
+    /// Whether every slot has been taken and has claimed every reward owed to it
+    pub fn is_settled(&self) -> bool {
+        self.remaining_slots() == 0
+            && self.outstanding_sol() == Some(0)
+            && self.outstanding_tokens(Some) == Some(0)
+    }
+}
+
+/// A reward level beside the base reward, such as full completion or a
+/// high-quality response, chosen by the verifier's attestation
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct RewardTier {
+    pub sol_reward_amount: u64,
+    pub token_reward_amount: u64,
+    /// Participants that may claim at this tier
+    pub max_participants: u32,
+    /// Participants that have claimed at this tier
+    pub participants: u32,
+    /// Participants at this tier that have claimed the SOL reward
+    pub sol_claims: u32,
+    /// Participants at this tier that have claimed the token reward
+    pub token_claims: u32,
+}
+
+impl RewardTier {
+    pub fn new(sol_reward_amount: u64, token_reward_amount: u64, max_participants: u32) -> Self {
+        Self {
+            sol_reward_amount,
+            token_reward_amount,
+            max_participants,
+            ..Self::default()
+        }
+    }
+}
+
+/// Bonus paid to a participant for each participant who names them as referrer
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct ReferralBonus {
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    /// Bonuses a single referrer can be paid; zero disables referral bonuses
+    pub max_per_referrer: u32,
+    _padding: [u8; 4],
+}
+
+impl ReferralBonus {
+    pub fn new(sol_amount: u64, token_amount: u64, max_per_referrer: u32) -> Self {
+        Self {
+            sol_amount,
+            token_amount,
+            max_per_referrer,
+            ..Self::default()
         }
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+/// What an early-bird reward curve decays with
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecayKind {
+    /// Every participant gets the full reward
+    None,
+    /// The reward falls evenly from full to the floor over one period
+    Linear,
+    /// The reward drops by a fixed cut at the end of every period, down to the floor
+    Step,
+}
+
+/// What an early-bird reward curve is measured in
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecayBasis {
+    /// Participants who took a slot before the claimant
+    Participants,
+    /// Seconds between `starts_at` and the claimant's first claim
+    Time,
+}
+
+/// Early-bird schedule scaling every reward by when the participant first claims
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct RewardCurve {
+    /// Participants or seconds a linear decay takes, or between two steps
+    pub period: u64,
+    /// Lowest share of the reward paid, in basis points
+    pub floor_bps: u16,
+    /// Cut per elapsed period for step decay, in basis points
+    pub step_bps: u16,
+    kind: u8,
+    basis: u8,
+    _padding: [u8; 2],
+}
+
+impl RewardCurve {
+    pub fn new(
+        kind: DecayKind,
+        basis: DecayBasis,
+        period: u64,
+        floor_bps: u16,
+        step_bps: u16,
+    ) -> Result<Self, SurveyError> {
+        if (kind != DecayKind::None && period == 0)
+            || floor_bps > FULL_REWARD_BPS
+            || step_bps > FULL_REWARD_BPS
+        {
+            return Err(SurveyError::InvalidRewardCurve);
+        }
+        
+        Ok(Self {
+            period,
+            floor_bps,
+            step_bps,
+            kind: kind as u8,
+            basis: basis as u8,
+            ..Self::default()
+        })
+    }
+    
+    pub fn kind(&self) -> DecayKind {
+        match self.kind {
+            1 => DecayKind::Linear,
+            2 => DecayKind::Step,
+            _ => DecayKind::None,
+        }
+    }
+    
+    pub fn basis(&self) -> DecayBasis {
+        match self.basis {
+            1 => DecayBasis::Time,
+            _ => DecayBasis::Participants,
+        }
+    }
+    
+    /// Share of the reward, in basis points, for a participant who first claims
+    /// after `participants` others and `elapsed` seconds after the survey starts
+    pub fn reward_bps(&self, participants: u32, elapsed: i64) -> u16 {
+        let progress = match self.basis() {
+            DecayBasis::Participants => participants as u64,
+            DecayBasis::Time => elapsed.max(0) as u64,
+        };
+        
+        let decay = match self.kind() {
+            DecayKind::None => return FULL_REWARD_BPS,
+            DecayKind::Linear => {
+                let range = (FULL_REWARD_BPS - self.floor_bps) as u128;
+                (range * progress.min(self.period) as u128 / self.period as u128) as u64
+            }
+            DecayKind::Step => (progress / self.period).saturating_mul(self.step_bps as u64),
+        };
+        
+        (FULL_REWARD_BPS as u64)
+            .saturating_sub(decay)
+            .max(self.floor_bps as u64) as u16
+    }
+}
+
+/// Scale `amount` by a share in basis points, rounding down
+pub fn apply_reward_bps(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / FULL_REWARD_BPS as u128) as u64
+}
+
+/// A participant's claim record, read and written in place
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct ParticipantAccount {
-    pub is_initialized: bool,
-    pub survey_id: String,
+    pub discriminator: [u8; DISCRIMINATOR_LEN],
+    pub version: u8,
+    pub bump: u8,
+    pub has_claimed_sol: PodBool,
+    pub has_claimed_token: PodBool,
+    pub has_received_nft: PodBool,
+    has_nft_leaf: PodBool,
+    /// Reward tier the participant claims at, fixed on their first claim
+    pub reward_tier: u8,
+    /// Share of the tier's rewards paid under the survey's reward curve,
+    /// fixed on the first claim
+    pub reward_bps: PodU16,
+    has_lottery_entry: PodBool,
+    lottery_entry: PodU32,
+    /// Whether the referrer has been paid the bonus for this participant
+    pub referral_bonus_paid: PodBool,
+    /// Referral bonuses this participant has been paid as a referrer
+    pub referral_bonuses: PodU32,
+    /// Referrals recorded naming this participant, at most the survey's
+    /// `max_per_referrer`
+    pub referrals: PodU32,
+    _padding: [u8; 1],
+    pub survey: Pubkey,
     pub participant: Pubkey,
-    pub has_claimed_sol: bool,
-    pub has_claimed_token: bool,
-    pub has_received_nft: bool,
-    pub claimed_at: Option<UnixTimestamp>,
+    pub response_hash: [u8; 32],
+    /// Participant who referred this one, named on the first claim
+    pub referrer: OptionalNonZeroPubkey,
+    nft_leaf_index: u64,
+    /// Unix timestamp of the first claim, zero until then
+    pub claimed_at: UnixTimestamp,
 }
 
 impl ParticipantAccount {
-    pub const LEN: usize = 1 + // is_initialized
-        4 + 64 + // survey_id (max 64 chars)
-        32 + // participant
-        1 + // has_claimed_sol
-        1 + // has_claimed_token
-        1 + // has_received_nft
-        1 + 8; // claimed_at (Option)
-    
-    pub fn new(survey_id: String, participant: Pubkey) -> Self {
+    pub const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = [239, 31, 144, 66, 245, 178, 84, 109];
+    pub const VERSION: u8 = 2;
+    
+    pub const LEN: usize = std::mem::size_of::<Self>();
+    
+    pub fn new(survey: Pubkey, participant: Pubkey, bump: u8) -> Self {
         Self {
-            is_initialized: true,
-            survey_id,
+            discriminator: Self::DISCRIMINATOR,
+            version: Self::VERSION,
+            survey,
             participant,
-            has_claimed_sol: false,
-            has_claimed_token: false,
-            has_received_nft: false,
-            claimed_at: None,
+            bump,
+            ..Zeroable::zeroed()
         }
     }
-}
\ No newline at end of file
+    
+    /// Leaf index of the participant's compressed NFT, if one was appended
+    pub fn nft_leaf_index(&self) -> Option<u64> {
+        bool::from(self.has_nft_leaf).then_some(self.nft_leaf_index)
+    }
+    
+    /// Whether the participant has claimed before and so holds a survey slot
+    pub fn has_claimed(&self) -> bool {
+        bool::from(self.has_claimed_sol) || bool::from(self.has_claimed_token)
+    }
+    
+    pub fn set_nft_leaf_index(&mut self, index: u64) {
+        self.nft_leaf_index = index;
+        self.has_nft_leaf = true.into();
+    }
+    
+    /// Index of the participant's entry in a lottery survey, if they entered
+    pub fn lottery_entry(&self) -> Option<u32> {
+        bool::from(self.has_lottery_entry).then_some(self.lottery_entry.into())
+    }
+    
+    pub fn set_lottery_entry(&mut self, entry: u32) {
+        self.lottery_entry = entry.into();
+        self.has_lottery_entry = true.into();
+    }
+}
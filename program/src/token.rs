@@ -0,0 +1,91 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Epoch,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+        BaseStateWithExtensions, ExtensionType,
+        StateWithExtensions,
+    },
+    state::{Account, Mint},
+};
+
+use crate::{error::SurveyError, validation::check_token_owned};
+
+/// Mint extensions that leave pool transfers working as plain `transfer_checked`
+/// calls. Anything else, such as a permanent delegate that could drain the
+/// pool or a transfer hook that needs extra accounts, is refused.
+pub const SUPPORTED_MINT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::InterestBearingConfig,
+];
+
+/// Check that a reward mint only uses supported extensions and return its decimals
+pub fn reward_mint_decimals(mint: &AccountInfo) -> Result<u8, ProgramError> {
+    check_token_owned(mint)?;
+    
+    let data = mint.data.borrow();
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    
+    let extensions = state.get_extension_types()?;
+    if extensions
+        .iter()
+        .any(|extension| !SUPPORTED_MINT_EXTENSIONS.contains(extension))
+    {
+        return Err(SurveyError::UnsupportedMintExtension.into());
+    }
+    
+    Ok(state.base.decimals)
+}
+
+/// Amount to send so the recipient receives `net_amount` after any transfer fee
+pub fn gross_transfer_amount(
+    mint: &AccountInfo,
+    net_amount: u64,
+    epoch: Epoch,
+) -> Result<u64, ProgramError> {
+    let data = mint.data.borrow();
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_inverse_epoch_fee(epoch, net_amount)
+            .ok_or(SurveyError::Overflow)?,
+        Err(_) => 0,
+    };
+    
+    Ok(net_amount.checked_add(fee).ok_or(SurveyError::Overflow)?)
+}
+
+/// Balance of an SPL Token or Token-2022 account
+pub fn token_account_amount(account: &AccountInfo) -> Result<u64, ProgramError> {
+    let data = account.data.borrow();
+    Ok(StateWithExtensions::<Account>::unpack(&data)?.base.amount)
+}
+
+/// Mint and owner of an SPL Token or Token-2022 account
+pub fn token_account_mint_and_owner(
+    account: &AccountInfo,
+) -> Result<(Pubkey, Pubkey), ProgramError> {
+    check_token_owned(account)?;
+    
+    let data = account.data.borrow();
+    let state = StateWithExtensions::<Account>::unpack(&data)?;
+    Ok((state.base.mint, state.base.owner))
+}
+
+/// Transfer fees withheld in a Token-2022 account, which block closing it until harvested
+pub fn withheld_transfer_fees(account: &AccountInfo) -> Result<u64, ProgramError> {
+    let data = account.data.borrow();
+    let state = StateWithExtensions::<Account>::unpack(&data)?;
+    
+    Ok(match state.get_extension::<TransferFeeAmount>() {
+        Ok(fees) => fees.withheld_amount.into(),
+        Err(_) => 0,
+    })
+}
@@ -0,0 +1,213 @@
+use std::{
+    cell::{Ref, RefMut},
+    mem::size_of,
+};
+
+use bytemuck::Pod;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::SurveyError,
+    pda::{participant_address, survey_address},
+    state::{
+        ParticipantAccount, SurveyAccount, DISCRIMINATOR_LEN, MAX_SURVEY_ID_LEN,
+        MAX_SURVEY_TITLE_LEN,
+    },
+};
+
+/// Require `account` to have signed the transaction
+pub fn check_signer(account: &AccountInfo) -> ProgramResult {
+    if !account.is_signer {
+        return Err(SurveyError::MissingSigner.into());
+    }
+    Ok(())
+}
+
+/// Require `account` to be passed as writable
+pub fn check_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        return Err(SurveyError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// Require `account` to be the program `program_id`
+pub fn check_program(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if account.key != program_id {
+        return Err(SurveyError::InvalidProgramId.into());
+    }
+    Ok(())
+}
+
+/// Require `account` to be either the SPL Token or the Token-2022 program
+pub fn check_token_program(account: &AccountInfo) -> ProgramResult {
+    if *account.key != spl_token::id() && *account.key != spl_token_2022::id() {
+        return Err(SurveyError::InvalidProgramId.into());
+    }
+    Ok(())
+}
+
+/// Require `account` to be the sysvar `sysvar_id`
+pub fn check_sysvar(account: &AccountInfo, sysvar_id: &Pubkey) -> ProgramResult {
+    if account.key != sysvar_id {
+        return Err(SurveyError::InvalidSysvar.into());
+    }
+    Ok(())
+}
+
+/// Require `account` to be owned by `owner`
+pub fn check_account_owner(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner != owner {
+        return Err(SurveyError::InvalidAccountOwner.into());
+    }
+    Ok(())
+}
+
+/// Require `account` to be owned by the SPL Token or the Token-2022 program
+pub fn check_token_owned(account: &AccountInfo) -> ProgramResult {
+    if *account.owner != spl_token::id() && *account.owner != spl_token_2022::id() {
+        return Err(SurveyError::InvalidAccountOwner.into());
+    }
+    Ok(())
+}
+
+/// Check a survey ID supplied to `InitializeSurvey`.
+///
+/// A plain ID is used as a seed as is, so it must be 1 to 32 bytes of ASCII
+/// letters, digits, `-` and `_`. A title that is hashed into the seed may be
+/// any printable text up to `MAX_SURVEY_TITLE_LEN` bytes, but without leading
+/// or trailing whitespace so clients hashing a trimmed title find the survey.
+pub fn check_survey_id(survey_id: &str, hashed: bool) -> ProgramResult {
+    let valid = if hashed {
+        !survey_id.is_empty()
+            && survey_id.len() <= MAX_SURVEY_TITLE_LEN
+            && survey_id.trim() == survey_id
+            && !survey_id.chars().any(char::is_control)
+    } else {
+        !survey_id.is_empty()
+            && survey_id.len() <= MAX_SURVEY_ID_LEN
+            && survey_id
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+    };
+    
+    if !valid {
+        return Err(SurveyError::InvalidSurveyId.into());
+    }
+    Ok(())
+}
+
+/// Check an account's discriminator and return its layout version, which
+/// loaders match on to read accounts written under older layouts
+pub fn account_version(
+    data: &[u8],
+    discriminator: &[u8; DISCRIMINATOR_LEN],
+) -> Result<u8, ProgramError> {
+    if data.get(..DISCRIMINATOR_LEN) != Some(&discriminator[..]) {
+        return Err(SurveyError::InvalidAccountData.into());
+    }
+    
+    data.get(DISCRIMINATOR_LEN)
+        .copied()
+        .ok_or_else(|| SurveyError::InvalidAccountData.into())
+}
+
+/// View the start of `data` as a `T`, failing if it is too short or misaligned
+fn cast_account_data<T: Pod>(data: &[u8]) -> Result<&T, ProgramError> {
+    data.get(..size_of::<T>())
+        .and_then(|data| bytemuck::try_from_bytes(data).ok())
+        .ok_or_else(|| SurveyError::InvalidAccountData.into())
+}
+
+/// Check that `account` is a live survey account of this program at its own PDA
+fn check_survey(program_id: &Pubkey, account: &AccountInfo) -> ProgramResult {
+    check_account_owner(account, program_id)?;
+    
+    let data = account.try_borrow_data()?;
+    let survey: &SurveyAccount = match account_version(&data, &SurveyAccount::DISCRIMINATOR)? {
+        SurveyAccount::VERSION => cast_account_data(&data)?,
+        _ => return Err(SurveyError::UnsupportedAccountVersion.into()),
+    };
+    
+    if survey_address(program_id, &survey.creator, survey.survey_id(), survey.bump)?
+        != *account.key
+    {
+        return Err(SurveyError::InvalidAccountAddress.into());
+    }
+    
+    Ok(())
+}
+
+/// Borrow a survey in place for reading
+pub fn load_survey<'b>(
+    program_id: &Pubkey,
+    account: &'b AccountInfo,
+) -> Result<Ref<'b, SurveyAccount>, ProgramError> {
+    check_survey(program_id, account)?;
+    Ok(Ref::map(account.try_borrow_data()?, |data| {
+        bytemuck::from_bytes(&data[..SurveyAccount::LEN])
+    }))
+}
+
+/// Borrow a writable survey in place for modification. The borrow must be
+/// released before any CPI that takes the survey account.
+pub fn load_survey_mut<'b>(
+    program_id: &Pubkey,
+    account: &'b AccountInfo,
+) -> Result<RefMut<'b, SurveyAccount>, ProgramError> {
+    check_writable(account)?;
+    check_survey(program_id, account)?;
+    Ok(RefMut::map(account.try_borrow_mut_data()?, |data| {
+        bytemuck::from_bytes_mut(&mut data[..SurveyAccount::LEN])
+    }))
+}
+
+/// Borrow a participant record of `survey` in place for modification after
+/// checking it is a writable participant account of this program at its own PDA
+pub fn load_participant_mut<'b>(
+    program_id: &Pubkey,
+    survey: &Pubkey,
+    account: &'b AccountInfo,
+) -> Result<RefMut<'b, ParticipantAccount>, ProgramError> {
+    if account.data_is_empty() {
+        return Err(SurveyError::NotInitialized.into());
+    }
+    
+    check_account_owner(account, program_id)?;
+    check_writable(account)?;
+    
+    {
+        let data = account.try_borrow_data()?;
+        let participant: &ParticipantAccount =
+            match account_version(&data, &ParticipantAccount::DISCRIMINATOR)? {
+                ParticipantAccount::VERSION => cast_account_data(&data)?,
+                _ => return Err(SurveyError::UnsupportedAccountVersion.into()),
+            };
+        
+        if participant_address(program_id, survey, &participant.participant, participant.bump)?
+            != *account.key
+        {
+            return Err(SurveyError::InvalidAccountAddress.into());
+        }
+    }
+    
+    Ok(RefMut::map(account.try_borrow_mut_data()?, |data| {
+        bytemuck::from_bytes_mut(&mut data[..ParticipantAccount::LEN])
+    }))
+}
+
+/// Write the initial state of an account the program has just created
+pub fn init_account<T: Pod>(account: &AccountInfo, state: &T) -> ProgramResult {
+    let mut data = account.try_borrow_mut_data()?;
+    let slot = data
+        .get_mut(..size_of::<T>())
+        .and_then(|data| bytemuck::try_from_bytes_mut::<T>(data).ok())
+        .ok_or(SurveyError::InvalidAccountData)?;
+    *slot = *state;
+    Ok(())
+}
@@ -0,0 +1,12 @@
+#!/bin/bash
+
+# Dump the on-chain programs the integration tests load from this directory.
+# The binaries are not committed; rerun this to refresh them.
+set -euo pipefail
+
+cd "$(dirname "$0")"
+
+# Metaplex Token Metadata, used by the NFT and collection tests
+solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s mpl_token_metadata.so
+
+echo "Fixtures written to $(pwd)"
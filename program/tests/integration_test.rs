@@ -1,25 +1,92 @@
 use solana_program_test::*;
 use solana_sdk::{
     account::Account,
-    instruction::{AccountMeta, Instruction},
+    account_info::AccountInfo,
+    clock::Clock,
+    ed25519_instruction::new_ed25519_instruction,
+    entrypoint::ProgramResult,
+    hash::{hashv, Hash},
+    instruction::{AccountMeta, Instruction, InstructionError},
     program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
     signature::Keypair,
     signer::Signer,
-    system_instruction,
-    transaction::Transaction,
+    slot_hashes::SlotHashes,
+    sysvar,
+    transaction::{Transaction, TransactionError},
 };
-use spl_token::state::Mint;
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account,
+};
+use spl_token_2022::{
+    extension::{
+        non_transferable::{NonTransferable, NonTransferableAccount},
+        permanent_delegate::PermanentDelegate,
+        transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensionsMut, StateWithExtensionsOwned,
+    },
+    error::TokenError,
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use spl_token_metadata_interface::state::TokenMetadata;
 use borsh::BorshSerialize;
+use mpl_token_metadata::accounts::{MasterEdition, Metadata};
+use std::path::Path;
 
 use solbridge_rewards::{
-    instruction::SurveyInstruction,
-    state::{SurveyAccount, ParticipantAccount},
+    attestation::attestation_message,
+    compression::{ACCOUNT_COMPRESSION_PROGRAM_ID, NOOP_PROGRAM_ID},
+    error::SurveyError,
+    lottery::{seed_commitment, LOTTERY_DRAW_DELAY_SLOTS, LOTTERY_REVEAL_WINDOW},
+    instruction::{
+        ReferralBonusConfig, RewardCurveConfig, RewardSelector, RewardTierConfig,
+        SurveyInstruction, SurveyUpdate,
+    },
+    pda::{
+        find_collection_mint_address, find_nft_mint_address, find_participant_address,
+        find_hashed_survey_address, find_survey_address, find_vault_authority_address,
+        hashed_survey_id,
+    },
+    state::{
+        DecayBasis, DecayKind, ParticipantAccount, SurveyAccount, CLOSED_SURVEY_DISCRIMINATOR,
+        DISCRIMINATOR_LEN,
+    },
 };
 
-#[tokio::test]
-async fn test_initialize_survey() {
+const OWNER_TOKEN_BALANCE: u64 = 1_000_000_000_000;
+const RESPONSE_HASH: [u8; 32] = [7; 32];
+
+struct TestSurvey {
+    program_id: Pubkey,
+    owner: Keypair,
+    verifier: Keypair,
+    token_mint: Pubkey,
+    token_program: Pubkey,
+    owner_token_account: Pubkey,
+    survey_id: String,
+    hash_survey_id: bool,
+    lottery_commitment: Option<[u8; 32]>,
+    survey_account: Pubkey,
+    vault_authority: Pubkey,
+    token_pool: Pubkey,
+}
+
+/// Build a `ProgramTest` with a funded survey owner, a reward mint and an
+/// owner token account holding `OWNER_TOKEN_BALANCE` tokens.
+fn program_test(survey_id: &str) -> (ProgramTest, TestSurvey) {
+    program_test_with_mint(survey_id, spl_token::id(), &[])
+}
+
+/// Like `program_test`, with the reward mint owned by `token_program` and
+/// carrying `mint_extensions`. A transfer fee is 1% and a permanent delegate
+/// is the owner.
+fn program_test_with_mint(
+    survey_id: &str,
+    token_program: Pubkey,
+    mint_extensions: &[ExtensionType],
+) -> (ProgramTest, TestSurvey) {
     let program_id = Pubkey::new_unique();
     let mut program_test = ProgramTest::new(
         "solbridge_rewards",
@@ -29,9 +96,7 @@ async fn test_initialize_survey() {
     
     // Create test accounts
     let owner = Keypair::new();
-    let survey_account = Keypair::new();
-    let token_mint = Keypair::new();
-    let token_pool = Keypair::new();
+    let token_mint = Pubkey::new_unique();
     
     // Fund owner account
     program_test.add_account(
@@ -42,64 +107,2901 @@ async fn test_initialize_survey() {
         },
     );
     
-    // Start test
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    // Create reward token mint
+    let mint = Mint {
+        mint_authority: Some(owner.pubkey()).into(),
+        supply: OWNER_TOKEN_BALANCE,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: None.into(),
+    };
+    let mint_len = ExtensionType::try_calculate_account_len::<Mint>(mint_extensions).unwrap();
+    let mut mint_data = vec![0; mint_len];
+    if mint_extensions.is_empty() {
+        Mint::pack(mint, &mut mint_data).unwrap();
+    } else {
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut mint_data).unwrap();
+        for extension in mint_extensions {
+            match extension {
+                ExtensionType::TransferFeeConfig => {
+                    let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+                    for fee in [&mut config.older_transfer_fee, &mut config.newer_transfer_fee] {
+                        fee.transfer_fee_basis_points = 100.into();
+                        fee.maximum_fee = u64::MAX.into();
+                    }
+                }
+                ExtensionType::PermanentDelegate => {
+                    state.init_extension::<PermanentDelegate>(true).unwrap().delegate =
+                        Some(owner.pubkey()).try_into().unwrap();
+                }
+                _ => unimplemented!("{:?}", extension),
+            }
+        }
+        state.base = mint;
+        state.pack_base();
+        state.init_account_type().unwrap();
+    }
+    program_test.add_account(
+        token_mint,
+        Account {
+            lamports: Rent::default().minimum_balance(mint_len),
+            data: mint_data,
+            owner: token_program,
+            ..Default::default()
+        },
+    );
     
-    // Create initialize survey instruction
-    let survey_id = "test_survey_001".to_string();
-    let sol_reward_amount = 1_000_000; // 0.001 SOL
-    let token_reward_amount = 100_000_000; // 100 tokens
-    let max_participants = 1000;
+    // Create owner token account holding the whole supply
+    let owner_token_account =
+        get_associated_token_address_with_program_id(&owner.pubkey(), &token_mint, &token_program);
+    let token_account = TokenAccount {
+        mint: token_mint,
+        owner: owner.pubkey(),
+        amount: OWNER_TOKEN_BALANCE,
+        state: AccountState::Initialized,
+        ..Default::default()
+    };
+    let account_extensions = ExtensionType::get_required_init_account_extensions(mint_extensions);
+    let account_len =
+        ExtensionType::try_calculate_account_len::<TokenAccount>(&account_extensions).unwrap();
+    let mut token_data = vec![0; account_len];
+    if account_extensions.is_empty() {
+        TokenAccount::pack(token_account, &mut token_data).unwrap();
+    } else {
+        let mut state =
+            StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut token_data).unwrap();
+        for extension in &account_extensions {
+            match extension {
+                ExtensionType::TransferFeeAmount => {
+                    state.init_extension::<TransferFeeAmount>(true).unwrap();
+                }
+                _ => unimplemented!("{:?}", extension),
+            }
+        }
+        state.base = token_account;
+        state.pack_base();
+        state.init_account_type().unwrap();
+    }
+    program_test.add_account(
+        owner_token_account,
+        Account {
+            lamports: Rent::default().minimum_balance(account_len),
+            data: token_data,
+            owner: token_program,
+            ..Default::default()
+        },
+    );
     
-    let init_ix = Instruction {
+    let (survey_account, _) = find_survey_address(&program_id, &owner.pubkey(), survey_id);
+    let (vault_authority, _) = find_vault_authority_address(&program_id, &survey_account);
+    let token_pool =
+        get_associated_token_address_with_program_id(&vault_authority, &token_mint, &token_program);
+    
+    let survey = TestSurvey {
         program_id,
+        owner,
+        verifier: Keypair::new(),
+        token_mint,
+        token_program,
+        owner_token_account,
+        survey_id: survey_id.to_string(),
+        hash_survey_id: false,
+        lottery_commitment: None,
+        survey_account,
+        vault_authority,
+        token_pool,
+    };
+    
+    (program_test, survey)
+}
+
+/// Like `program_test`, for a survey keyed by the hash of a free-form title
+fn program_test_with_title(title: &str) -> (ProgramTest, TestSurvey) {
+    let (program_test, mut survey) = program_test("placeholder");
+    
+    let (survey_account, _) =
+        find_hashed_survey_address(&survey.program_id, &survey.owner.pubkey(), title);
+    let (vault_authority, _) = find_vault_authority_address(&survey.program_id, &survey_account);
+    survey.survey_id = title.to_string();
+    survey.hash_survey_id = true;
+    survey.survey_account = survey_account;
+    survey.vault_authority = vault_authority;
+    survey.token_pool = get_associated_token_address_with_program_id(
+        &vault_authority,
+        &survey.token_mint,
+        &survey.token_program,
+    );
+    
+    (program_test, survey)
+}
+
+/// Load the Token Metadata program dumped by `tests/fixtures/fetch.sh`. Tests
+/// that need it are ignored by default and run with `--include-ignored`.
+fn add_token_metadata_program(program_test: &mut ProgramTest) {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mpl_token_metadata.so");
+    assert!(
+        fixture.exists(),
+        "missing {}, run tests/fixtures/fetch.sh",
+        fixture.display()
+    );
+    program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+}
+
+/// Anchor's entrypoint ties the account slice to the accounts' own lifetime,
+/// which the builtin signature `processor!` expects does not
+fn process_account_compression<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // SAFETY: only the slice's lifetime changes. The slice and the account
+    // infos both live in the runtime's invocation context for the whole call,
+    // and neither escapes `entry`, which returns before they are dropped.
+    let accounts: &'a [AccountInfo<'a>] = unsafe { std::mem::transmute(accounts) };
+    spl_account_compression::entry(program_id, accounts, instruction_data)
+}
+
+/// Add a funded participant wallet to the test environment
+fn add_participant(program_test: &mut ProgramTest) -> Keypair {
+    let participant = Keypair::new();
+    program_test.add_account(
+        participant.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Default::default()
+        },
+    );
+    participant
+}
+
+fn initialize_survey_ix(
+    survey: &TestSurvey,
+    sol_reward_amount: u64,
+    token_reward_amount: u64,
+    max_participants: u32,
+) -> Instruction {
+    initialize_survey_with_schedule_ix(
+        survey,
+        sol_reward_amount,
+        token_reward_amount,
+        max_participants,
+        (0, i64::MAX, i64::MAX),
+    )
+}
+
+fn initialize_survey_with_schedule_ix(
+    survey: &TestSurvey,
+    sol_reward_amount: u64,
+    token_reward_amount: u64,
+    max_participants: u32,
+    (starts_at, ends_at, claim_deadline): (i64, i64, i64),
+) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
         accounts: vec![
-            AccountMeta::new(owner.pubkey(), true),
-            AccountMeta::new(survey_account.pubkey(), false),
-            AccountMeta::new_readonly(token_mint.pubkey(), false),
-            AccountMeta::new(token_pool.pubkey(), false),
+            AccountMeta::new(survey.owner.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new_readonly(survey.token_mint, false),
+            AccountMeta::new(survey.token_pool, false),
             AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
             AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            AccountMeta::new_readonly(survey.token_program, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
         ],
         data: SurveyInstruction::InitializeSurvey {
-            survey_id: survey_id.clone(),
+            survey_id: survey.survey_id.clone(),
             sol_reward_amount,
             token_reward_amount,
             max_participants,
+            verifier: survey.verifier.pubkey(),
+            starts_at,
+            ends_at,
+            claim_deadline,
+            hash_survey_id: survey.hash_survey_id,
+            lottery_commitment: survey.lottery_commitment,
+        }.try_to_vec().unwrap(),
+    }
+}
+
+fn fund_survey_ix(survey: &TestSurvey, sol_amount: u64, token_amount: u64) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new(survey.owner.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(survey.owner_token_account, false),
+            AccountMeta::new(survey.token_pool, false),
+            AccountMeta::new_readonly(survey.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(survey.token_mint, false),
+        ],
+        data: SurveyInstruction::FundSurvey {
+            survey_id: survey.survey_id.clone(),
+            sol_amount,
+            token_amount,
+        }.try_to_vec().unwrap(),
+    }
+}
+
+fn claim_reward_ix(
+    survey: &TestSurvey,
+    participant: &Pubkey,
+    authorization: AccountMeta,
+    expiry: i64,
+) -> Instruction {
+    claim_selected_reward_ix(survey, participant, authorization, expiry, RewardSelector::Both, 0, None)
+}
+
+fn claim_selected_reward_ix(
+    survey: &TestSurvey,
+    participant: &Pubkey,
+    authorization: AccountMeta,
+    expiry: i64,
+    reward: RewardSelector,
+    reward_tier: u8,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    let (participant_account, _) =
+        find_participant_address(&survey.program_id, &survey.survey_account, participant);
+    let mut instruction = Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new(*participant, true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(participant_account, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(
+                    participant,
+                    &survey.token_mint,
+                    &survey.token_program,
+                ),
+                false,
+            ),
+            AccountMeta::new(survey.token_pool, false),
+            AccountMeta::new_readonly(survey.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            authorization,
+            AccountMeta::new_readonly(survey.token_mint, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: SurveyInstruction::ClaimReward {
+            survey_id: survey.survey_id.clone(),
+            response_hash: RESPONSE_HASH,
+            expiry,
+            reward,
+            reward_tier,
+            referrer,
         }.try_to_vec().unwrap(),
     };
+    if let Some(referrer) = referrer {
+        let (referrer_account, _) =
+            find_participant_address(&survey.program_id, &survey.survey_account, &referrer);
+        instruction.accounts.push(AccountMeta::new(referrer_account, false));
+    }
+    instruction
+}
+
+/// Claim with the survey verifier co-signing
+async fn claim_reward(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    survey: &TestSurvey,
+    participant: &Keypair,
+) -> Result<(), BanksClientError> {
+    claim_selected_reward(banks_client, payer, survey, participant, RewardSelector::Both, 0).await
+}
+
+/// Claim the `reward` legs at `reward_tier` with the survey verifier co-signing
+async fn claim_selected_reward(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    survey: &TestSurvey,
+    participant: &Keypair,
+    reward: RewardSelector,
+    reward_tier: u8,
+) -> Result<(), BanksClientError> {
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            claim_selected_reward_ix(
+                survey,
+                &participant.pubkey(),
+                AccountMeta::new_readonly(survey.verifier.pubkey(), true),
+                0,
+                reward,
+                reward_tier,
+                None,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, participant, &survey.verifier], recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+/// Claim both rewards at the base tier naming `referrer`, with the survey verifier co-signing
+async fn claim_referred_reward(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    survey: &TestSurvey,
+    participant: &Keypair,
+    referrer: Pubkey,
+) -> Result<(), BanksClientError> {
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            claim_selected_reward_ix(
+                survey,
+                &participant.pubkey(),
+                AccountMeta::new_readonly(survey.verifier.pubkey(), true),
+                0,
+                RewardSelector::Both,
+                0,
+                Some(referrer),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, participant, &survey.verifier], recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+/// Collect `referrer`'s bonus for referring `referred`, with `payer` paying the fee
+async fn claim_referral_bonus(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    survey: &TestSurvey,
+    referrer: &Keypair,
+    referred: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let (referrer_account, _) =
+        find_participant_address(&survey.program_id, &survey.survey_account, &referrer.pubkey());
+    let (referred_account, _) =
+        find_participant_address(&survey.program_id, &survey.survey_account, referred);
+    let instruction = Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new(referrer.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(referrer_account, false),
+            AccountMeta::new(referred_account, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(
+                    &referrer.pubkey(),
+                    &survey.token_mint,
+                    &survey.token_program,
+                ),
+                false,
+            ),
+            AccountMeta::new(survey.token_pool, false),
+            AccountMeta::new_readonly(survey.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            AccountMeta::new_readonly(survey.token_mint, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: SurveyInstruction::ClaimReferralBonus {
+            survey_id: survey.survey_id.clone(),
+        }.try_to_vec().unwrap(),
+    };
+    
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    transaction.sign(&[payer, referrer], recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+/// Claim with an ed25519 attestation signed by `signer` instead of a co-signature
+async fn claim_reward_with_attestation(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    survey: &TestSurvey,
+    participant: &Keypair,
+    signer: &Keypair,
+    expiry: i64,
+) -> Result<(), BanksClientError> {
+    let message = attestation_message(
+        &survey.survey_account,
+        &participant.pubkey(),
+        &RESPONSE_HASH,
+        expiry,
+        0,
+    );
+    let signer = ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap();
+    
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            new_ed25519_instruction(&signer, &message),
+            claim_reward_ix(
+                survey,
+                &participant.pubkey(),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+                expiry,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, participant], recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+/// Enter a lottery survey with the survey verifier co-signing. `expiry` only
+/// varies the transaction so a repeated entry is not deduplicated.
+async fn enter_lottery(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    survey: &TestSurvey,
+    participant: &Keypair,
+    expiry: i64,
+) -> Result<(), BanksClientError> {
+    let (participant_account, _) =
+        find_participant_address(&survey.program_id, &survey.survey_account, &participant.pubkey());
+    let instruction = Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new(participant.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(participant_account, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(survey.verifier.pubkey(), true),
+        ],
+        data: SurveyInstruction::EnterLottery {
+            survey_id: survey.survey_id.clone(),
+            response_hash: RESPONSE_HASH,
+            expiry,
+        }.try_to_vec().unwrap(),
+    };
+    
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    transaction.sign(&[payer, participant, &survey.verifier], recent_blockhash);
+    banks_client.process_transaction(transaction).await
+}
+
+fn start_lottery_draw_ix(survey: &TestSurvey, seed: Option<[u8; 32]>) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![AccountMeta::new(survey.survey_account, false)],
+        data: SurveyInstruction::StartLotteryDraw {
+            survey_id: survey.survey_id.clone(),
+            seed,
+        }.try_to_vec().unwrap(),
+    }
+}
+
+fn draw_winners_ix(survey: &TestSurvey) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        ],
+        data: SurveyInstruction::DrawWinners {
+            survey_id: survey.survey_id.clone(),
+        }.try_to_vec().unwrap(),
+    }
+}
+
+/// Send a permissionless `instruction` paid by the context payer
+async fn send_unsigned(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+) -> Result<(), BanksClientError> {
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    transaction.sign(&[&context.payer], recent_blockhash);
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Hash of the first slot at or after `draw_slot` in the SlotHashes sysvar
+async fn draw_slot_hash(context: &mut ProgramTestContext, draw_slot: u64) -> [u8; 32] {
+    let slot_hashes = context.banks_client.get_sysvar::<SlotHashes>().await.unwrap();
+    let (_, slot_hash) = slot_hashes
+        .iter()
+        .rev()
+        .find(|(slot, _)| *slot >= draw_slot)
+        .unwrap();
+    slot_hash.to_bytes()
+}
+
+/// Warp to `slot` and set the clock to `unix_timestamp`
+async fn warp_to(context: &mut ProgramTestContext, slot: u64, unix_timestamp: i64) {
+    context.warp_to_slot(slot).unwrap();
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+fn assert_survey_error(result: Result<(), BanksClientError>, expected: SurveyError) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, expected as u32)
+        }
+        err => panic!("Unexpected error: {:?}", err),
+    }
+}
+
+fn close_survey_ix(survey: &TestSurvey, force: bool) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(survey.owner.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(survey.owner.pubkey(), false),
+            AccountMeta::new(survey.owner_token_account, false),
+            AccountMeta::new(survey.token_pool, false),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            AccountMeta::new_readonly(survey.token_program, false),
+            AccountMeta::new(survey.token_mint, false),
+        ],
+        data: SurveyInstruction::CloseSurvey {
+            survey_id: survey.survey_id.clone(),
+            force,
+        }.try_to_vec().unwrap(),
+    }
+}
+
+fn set_paused_ix(survey: &TestSurvey, authority: &Pubkey, paused: bool) -> Instruction {
+    let survey_id = survey.survey_id.clone();
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(survey.survey_account, false),
+        ],
+        data: if paused {
+            SurveyInstruction::PauseSurvey { survey_id }
+        } else {
+            SurveyInstruction::ResumeSurvey { survey_id }
+        }.try_to_vec().unwrap(),
+    }
+}
+
+fn update_survey_ix(survey: &TestSurvey, update: SurveyUpdate) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(survey.owner.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new_readonly(survey.token_mint, false),
+        ],
+        data: SurveyInstruction::UpdateSurvey {
+            survey_id: survey.survey_id.clone(),
+            update,
+        }.try_to_vec().unwrap(),
+    }
+}
+
+fn distribute_nft_ix(
+    survey: &TestSurvey,
+    authority: &Pubkey,
+    participant: &Pubkey,
+    collection: Option<Pubkey>,
+) -> Instruction {
+    let (participant_account, _) =
+        find_participant_address(&survey.program_id, &survey.survey_account, participant);
+    let (nft_mint, _) =
+        find_nft_mint_address(&survey.program_id, &survey.survey_account, participant);
+    let (metadata, _) = Metadata::find_pda(&nft_mint);
+    let (master_edition, _) = MasterEdition::find_pda(&nft_mint);
+    
+    let mut accounts = vec![
+        AccountMeta::new(*authority, true),
+        AccountMeta::new_readonly(survey.survey_account, false),
+        AccountMeta::new(participant_account, false),
+        AccountMeta::new_readonly(*participant, false),
+        AccountMeta::new(nft_mint, false),
+        AccountMeta::new(get_associated_token_address(participant, &nft_mint), false),
+        AccountMeta::new_readonly(survey.vault_authority, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(metadata, false),
+        AccountMeta::new(master_edition, false),
+        AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+    ];
+    if let Some(collection) = collection {
+        accounts.extend([
+            AccountMeta::new_readonly(collection, false),
+            AccountMeta::new(Metadata::find_pda(&collection).0, false),
+            AccountMeta::new_readonly(MasterEdition::find_pda(&collection).0, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ]);
+    }
+    
+    Instruction {
+        program_id: survey.program_id,
+        accounts,
+        data: SurveyInstruction::DistributeNft {
+            survey_id: survey.survey_id.clone(),
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+fn distribute_badge_ix(survey: &TestSurvey, participant: &Pubkey) -> Instruction {
+    let (participant_account, _) =
+        find_participant_address(&survey.program_id, &survey.survey_account, participant);
+    let (badge_mint, _) =
+        find_nft_mint_address(&survey.program_id, &survey.survey_account, participant);
+    
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new(survey.owner.pubkey(), true),
+            AccountMeta::new_readonly(survey.survey_account, false),
+            AccountMeta::new(participant_account, false),
+            AccountMeta::new_readonly(*participant, false),
+            AccountMeta::new(badge_mint, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(
+                    participant,
+                    &badge_mint,
+                    &spl_token_2022::id(),
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: SurveyInstruction::DistributeNft {
+            survey_id: survey.survey_id.clone(),
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+fn create_collection_ix(survey: &TestSurvey) -> Instruction {
+    let (collection_mint, _) =
+        find_collection_mint_address(&survey.program_id, &survey.survey_account);
+    
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new(survey.owner.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(collection_mint, false),
+            AccountMeta::new(
+                get_associated_token_address(&survey.vault_authority, &collection_mint),
+                false,
+            ),
+            AccountMeta::new(Metadata::find_pda(&collection_mint).0, false),
+            AccountMeta::new(MasterEdition::find_pda(&collection_mint).0, false),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+        ],
+        data: SurveyInstruction::CreateCollection {
+            survey_id: survey.survey_id.clone(),
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+/// Size of an account compression tree without a canopy: the header, the
+/// sequence number, active index and buffer size, the change log buffer and the
+/// rightmost proof
+fn merkle_tree_size(max_depth: usize, max_buffer_size: usize) -> usize {
+    let path_size = 32 * max_depth + 32 + 8;
+    56 + 24 + max_buffer_size * path_size + path_size
+}
+
+fn create_nft_tree_ix(survey: &TestSurvey, merkle_tree: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(survey.owner.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(*merkle_tree, false),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+            AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        ],
+        data: SurveyInstruction::CreateNftTree {
+            survey_id: survey.survey_id.clone(),
+            max_depth: 14,
+            max_buffer_size: 64,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+fn distribute_compressed_nft_ix(
+    survey: &TestSurvey,
+    merkle_tree: &Pubkey,
+    participant: &Pubkey,
+) -> Instruction {
+    let (participant_account, _) =
+        find_participant_address(&survey.program_id, &survey.survey_account, participant);
+    
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(survey.owner.pubkey(), true),
+            AccountMeta::new(survey.survey_account, false),
+            AccountMeta::new(participant_account, false),
+            AccountMeta::new(*merkle_tree, false),
+            AccountMeta::new_readonly(survey.vault_authority, false),
+            AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+            AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        ],
+        data: SurveyInstruction::DistributeCompressedNft {
+            survey_id: survey.survey_id.clone(),
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+async fn get_participant(banks_client: &mut BanksClient, address: Pubkey) -> ParticipantAccount {
+    let account = banks_client.get_account(address).await.unwrap().unwrap();
+    bytemuck::pod_read_unaligned(&account.data[..ParticipantAccount::LEN])
+}
+
+/// Build an owner-administration instruction signed by `signer` that only needs the survey account
+fn survey_admin_ix(survey: &TestSurvey, signer: &Pubkey, instruction: SurveyInstruction) -> Instruction {
+    Instruction {
+        program_id: survey.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(survey.survey_account, false),
+        ],
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+async fn get_survey(banks_client: &mut BanksClient, address: Pubkey) -> SurveyAccount {
+    let account = banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .expect("Survey account not found");
+    bytemuck::pod_read_unaligned(&account.data[..SurveyAccount::LEN])
+}
+
+async fn get_token_balance(banks_client: &mut BanksClient, address: Pubkey) -> u64 {
+    let account = banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .expect("Token account not found");
+    StateWithExtensionsOwned::<TokenAccount>::unpack(account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+#[tokio::test]
+async fn test_initialize_survey() {
+    let (program_test, survey) = program_test("test_survey_001");
+    
+    // Start test
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let sol_reward_amount = 1_000_000; // 0.001 SOL
+    let token_reward_amount = 100_000_000; // 100 tokens
+    let max_participants = 1000;
     
     // Create and send transaction
     let mut transaction = Transaction::new_with_payer(
-        &[init_ix],
+        &[initialize_survey_ix(&survey, sol_reward_amount, token_reward_amount, max_participants)],
         Some(&payer.pubkey()),
     );
-    transaction.sign(&[&payer, &owner], recent_blockhash);
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
     
     banks_client.process_transaction(transaction).await.unwrap();
     
     // Verify survey account was created
-    let survey = banks_client
-        .get_account(survey_account.pubkey())
+    let account = banks_client
+        .get_account(survey.survey_account)
         .await
         .unwrap()
         .expect("Survey account not found");
     
-    assert_eq!(survey.owner, program_id);
+    assert_eq!(account.owner, survey.program_id);
+    assert_eq!(account.data[..DISCRIMINATOR_LEN], SurveyAccount::DISCRIMINATOR);
+    assert_eq!(account.data[DISCRIMINATOR_LEN], SurveyAccount::VERSION);
+    
+    // Verify the token pool is owned by the vault authority
+    let pool = banks_client
+        .get_account(survey.token_pool)
+        .await
+        .unwrap()
+        .expect("Token pool not found");
+    let pool = TokenAccount::unpack(&pool.data).unwrap();
+    assert_eq!(pool.owner, survey.vault_authority);
+    assert_eq!(pool.mint, survey.token_mint);
 }
 
 #[tokio::test]
-async fn test_claim_reward() {
-    // Similar test setup for claiming rewards
-    // This would include creating a survey first, then testing the claim process
+async fn test_fund_survey() {
+    let (program_test, survey) = program_test("test_survey_fund");
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let sol_amount = 50_000_000;
+    let token_amount = 5_000_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 50),
+            fund_survey_ix(&survey, sol_amount, token_amount),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.total_sol_deposited, sol_amount);
+    assert_eq!(state.total_tokens_deposited, token_amount);
+    
+    let rent = banks_client.get_rent().await.unwrap();
+    let lamports = banks_client.get_balance(survey.survey_account).await.unwrap();
+    assert_eq!(lamports, rent.minimum_balance(SurveyAccount::LEN) + sol_amount);
+    assert_eq!(get_token_balance(&mut banks_client, survey.token_pool).await, token_amount);
+    
+    // Funding names the survey it expects to deposit into
+    let mut instruction = fund_survey_ix(&survey, sol_amount, 0);
+    instruction.data = SurveyInstruction::FundSurvey {
+        survey_id: "other_survey".to_string(),
+        sol_amount,
+        token_amount: 0,
+    }.try_to_vec().unwrap();
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    assert_survey_error(
+        banks_client.process_transaction(transaction).await,
+        SurveyError::SurveyNotFound,
+    );
 }
 
 #[tokio::test]
-async fn test_distribute_nft() {
-    // Test NFT distribution functionality
+async fn test_initialize_survey_rejects_overflowing_commitment() {
+    let (program_test, survey) = program_test("test_survey_overflow");
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_survey_ix(&survey, u64::MAX, 0, 2)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::InvalidRewardAmount);
 }
 
 #[tokio::test]
-async fn test_close_survey() {
-    // Test survey closing functionality
-}
\ No newline at end of file
+async fn test_initialize_survey_rejects_invalid_survey_id() {
+    let (program_test, mut survey) = program_test("test_survey_bad_id");
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let long_id = "a".repeat(33);
+    for survey_id in ["", "has space", "slash/ed", "caf\u{e9}", long_id.as_str()] {
+        survey.survey_id = survey_id.to_string();
+        let mut transaction = Transaction::new_with_payer(
+            &[initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+        
+        let result = banks_client.process_transaction(transaction).await;
+        assert_survey_error(result, SurveyError::InvalidSurveyId);
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_survey_with_hashed_title() {
+    let title = "What do you think of the new tram line in Lisbon?";
+    let (program_test, mut survey) = program_test_with_title(title);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let send = |instruction: Instruction| {
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+        transaction
+    };
+    
+    banks_client
+        .process_transaction(send(initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10)))
+        .await
+        .unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert!(bool::from(state.hashed_survey_id));
+    assert_eq!(state.survey_id(), hashed_survey_id(title));
+    assert!(state.matches_survey_id(title));
+    
+    // Later instructions name the survey by its title
+    banks_client
+        .process_transaction(send(set_paused_ix(&survey, &survey.owner.pubkey(), true)))
+        .await
+        .unwrap();
+    assert!(bool::from(get_survey(&mut banks_client, survey.survey_account).await.is_paused));
+    
+    // Titles are hashed as given, so untrimmed ones are refused
+    survey.survey_id = format!("{} ", title);
+    let result = banks_client
+        .process_transaction(send(initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10)))
+        .await;
+    assert_survey_error(result, SurveyError::InvalidSurveyId);
+}
+
+#[tokio::test]
+async fn test_claim_reward() {
+    let (mut program_test, survey) = program_test("test_survey_claim");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let sol_reward_amount = 1_000_000;
+    let token_reward_amount = 100_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, sol_reward_amount, token_reward_amount, 10),
+            fund_survey_ix(&survey, sol_reward_amount * 10, token_reward_amount * 10),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let balance_before = banks_client.get_balance(participant.pubkey()).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let rent = banks_client.get_rent().await.unwrap();
+    let balance_after = banks_client.get_balance(participant.pubkey()).await.unwrap();
+    // The participant pays rent for their participant record and token account
+    assert_eq!(
+        balance_after,
+        balance_before + sol_reward_amount
+            - rent.minimum_balance(ParticipantAccount::LEN)
+            - rent.minimum_balance(TokenAccount::LEN)
+    );
+    
+    let participant_token_account =
+        get_associated_token_address(&participant.pubkey(), &survey.token_mint);
+    assert_eq!(
+        get_token_balance(&mut banks_client, participant_token_account).await,
+        token_reward_amount
+    );
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.current_participants, 1);
+}
+
+#[tokio::test]
+async fn test_claim_reward_legs_independently() {
+    let (mut program_test, survey) = program_test("test_survey_legs");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let sol_reward_amount = 1_000_000;
+    let token_reward_amount = 100_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, sol_reward_amount, token_reward_amount, 1),
+            fund_survey_ix(&survey, sol_reward_amount, token_reward_amount),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let participant_token_account =
+        get_associated_token_address(&participant.pubkey(), &survey.token_mint);
+    
+    // The SOL leg alone takes the only slot without touching the token accounts
+    claim_selected_reward(&mut banks_client, &payer, &survey, &participant, RewardSelector::Sol, 0)
+        .await
+        .unwrap();
+    assert!(banks_client.get_account(participant_token_account).await.unwrap().is_none());
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.current_participants, 1);
+    assert_eq!(state.sol_claims, 1);
+    assert_eq!(state.token_claims, 0);
+    assert!(!state.is_settled());
+    
+    let result = claim_selected_reward(
+        &mut banks_client,
+        &payer,
+        &survey,
+        &participant,
+        RewardSelector::Sol,
+        0,
+    )
+    .await;
+    assert_survey_error(result, SurveyError::AlreadyClaimed);
+    
+    // The unclaimed token leg cannot be cut from under the participant
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(
+            &survey,
+            SurveyUpdate {
+                token_reward_amount: Some(0),
+                ..Default::default()
+            },
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::InvalidUpdate);
+    
+    // Claiming both later pays only the token leg, even though the survey is full
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    assert_eq!(
+        get_token_balance(&mut banks_client, participant_token_account).await,
+        token_reward_amount
+    );
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.current_participants, 1);
+    assert_eq!(state.token_claims, 1);
+    assert!(state.is_settled());
+    
+    let (participant_account, _) = find_participant_address(
+        &survey.program_id,
+        &survey.survey_account,
+        &participant.pubkey(),
+    );
+    let participant_state = get_participant(&mut banks_client, participant_account).await;
+    assert!(bool::from(participant_state.has_claimed_sol));
+    assert!(bool::from(participant_state.has_claimed_token));
+    
+    let result = claim_reward(&mut banks_client, &payer, &survey, &participant).await;
+    assert_survey_error(result, SurveyError::AlreadyClaimed);
+}
+
+#[tokio::test]
+async fn test_claim_reward_at_tier() {
+    let (mut program_test, survey) = program_test("test_survey_tiers");
+    let thoughtful = add_participant(&mut program_test);
+    let quick = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let base_sol = 1_000_000;
+    let tier_sol = 3_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, base_sol, 100_000_000, 3),
+            update_survey_ix(
+                &survey,
+                SurveyUpdate {
+                    reward_tiers: Some(vec![RewardTierConfig {
+                        sol_reward_amount: tier_sol,
+                        token_reward_amount: 300_000_000,
+                        max_participants: 1,
+                    }]),
+                    ..Default::default()
+                },
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // Every slot could be taken at the best-paying tier that has room
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.outstanding_sol(), Some(tier_sol + 2 * base_sol));
+    assert_eq!(state.outstanding_tokens(Some), Some(500_000_000));
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[fund_survey_ix(&survey, tier_sol + 2 * base_sol, 500_000_000)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_selected_reward(&mut banks_client, &payer, &survey, &thoughtful, RewardSelector::Token, 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_token_balance(
+            &mut banks_client,
+            get_associated_token_address(&thoughtful.pubkey(), &survey.token_mint),
+        )
+        .await,
+        300_000_000
+    );
+    
+    // Later legs stay at the tier fixed by the first claim
+    let result = claim_selected_reward(
+        &mut banks_client,
+        &payer,
+        &survey,
+        &thoughtful,
+        RewardSelector::Sol,
+        0,
+    )
+    .await;
+    assert_survey_error(result, SurveyError::InvalidRewardTier);
+    
+    let balance_before = banks_client.get_balance(thoughtful.pubkey()).await.unwrap();
+    claim_selected_reward(&mut banks_client, &payer, &survey, &thoughtful, RewardSelector::Sol, 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        banks_client.get_balance(thoughtful.pubkey()).await.unwrap(),
+        balance_before + tier_sol
+    );
+    
+    // The tier is capped at one participant, and tier 2 does not exist
+    let result =
+        claim_selected_reward(&mut banks_client, &payer, &survey, &quick, RewardSelector::Both, 1)
+            .await;
+    assert_survey_error(result, SurveyError::RewardTierFull);
+    
+    let result =
+        claim_selected_reward(&mut banks_client, &payer, &survey, &quick, RewardSelector::Both, 2)
+            .await;
+    assert_survey_error(result, SurveyError::InvalidRewardTier);
+    
+    claim_reward(&mut banks_client, &payer, &survey, &quick).await.unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.current_participants, 2);
+    assert_eq!(state.reward_tiers()[0].participants, 1);
+    assert_eq!(state.outstanding_sol(), Some(base_sol));
+    
+    // A tier with participants cannot be dropped
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(
+            &survey,
+            SurveyUpdate {
+                reward_tiers: Some(vec![]),
+                ..Default::default()
+            },
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::InvalidUpdate);
+}
+
+#[tokio::test]
+async fn test_claim_reward_decays_with_participants() {
+    let (mut program_test, survey) = program_test("test_survey_decay");
+    let participants: Vec<Keypair> = (0..3).map(|_| add_participant(&mut program_test)).collect();
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let token_reward_amount = 100_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 0, token_reward_amount, 3),
+            update_survey_ix(
+                &survey,
+                SurveyUpdate {
+                    reward_curve: Some(RewardCurveConfig {
+                        kind: DecayKind::Linear,
+                        basis: DecayBasis::Participants,
+                        period: 2,
+                        floor_bps: 5_000,
+                        step_bps: 0,
+                    }),
+                    ..Default::default()
+                },
+            ),
+            fund_survey_ix(&survey, 0, 3 * token_reward_amount),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // The reward falls from full to half over the first two participants
+    for (participant, expected) in participants.iter().zip([100_000_000, 75_000_000, 50_000_000]) {
+        claim_reward(&mut banks_client, &payer, &survey, participant).await.unwrap();
+        assert_eq!(
+            get_token_balance(
+                &mut banks_client,
+                get_associated_token_address(&participant.pubkey(), &survey.token_mint),
+            )
+            .await,
+            expected
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_claim_reward_decays_in_steps_over_time() {
+    let (mut program_test, survey) = program_test("test_survey_step_decay");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let now = banks_client
+        .get_sysvar::<solana_sdk::clock::Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+    let sol_reward_amount = 1_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_with_schedule_ix(
+                &survey,
+                sol_reward_amount,
+                0,
+                10,
+                (now - 2_500, now + 3_600, now + 7_200),
+            ),
+            update_survey_ix(
+                &survey,
+                SurveyUpdate {
+                    reward_curve: Some(RewardCurveConfig {
+                        kind: DecayKind::Step,
+                        basis: DecayBasis::Time,
+                        period: 1_000,
+                        floor_bps: 5_000,
+                        step_bps: 2_000,
+                    }),
+                    ..Default::default()
+                },
+            ),
+            fund_survey_ix(&survey, 10 * sol_reward_amount, 0),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // Two full periods have passed since the start, so two 20% cuts apply
+    let balance_before = banks_client.get_balance(participant.pubkey()).await.unwrap();
+    claim_selected_reward(&mut banks_client, &payer, &survey, &participant, RewardSelector::Sol, 0)
+        .await
+        .unwrap();
+    let rent = banks_client.get_rent().await.unwrap();
+    assert_eq!(
+        banks_client.get_balance(participant.pubkey()).await.unwrap(),
+        balance_before + sol_reward_amount * 6 / 10 - rent.minimum_balance(ParticipantAccount::LEN)
+    );
+}
+
+#[tokio::test]
+async fn test_lottery_draws_winners() {
+    let (mut program_test, mut survey) = program_test("test_survey_lottery");
+    let seed = [9; 32];
+    survey.lottery_commitment = Some(seed_commitment(&seed));
+    let participants: Vec<Keypair> = (0..5).map(|_| add_participant(&mut program_test)).collect();
+    let outsider = add_participant(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let sol_reward_amount = 1_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_with_schedule_ix(
+                &survey,
+                sol_reward_amount,
+                0,
+                2,
+                (clock.unix_timestamp - 100, clock.unix_timestamp + 3_600, clock.unix_timestamp + 7_200),
+            ),
+            fund_survey_ix(&survey, 2 * sol_reward_amount, 0),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    
+    for participant in &participants {
+        enter_lottery(&mut context.banks_client, &payer, &survey, participant, 0).await.unwrap();
+    }
+    assert_survey_error(
+        enter_lottery(&mut context.banks_client, &payer, &survey, &participants[0], 1).await,
+        SurveyError::AlreadyEntered,
+    );
+    assert_survey_error(
+        claim_reward(&mut context.banks_client, &payer, &survey, &participants[0]).await,
+        SurveyError::WinnersNotDrawn,
+    );
+    
+    // The draw can only start once entries have closed
+    assert_survey_error(
+        send_unsigned(&mut context, start_lottery_draw_ix(&survey, Some(seed))).await,
+        SurveyError::LotteryStillOpen,
+    );
+    
+    let ends_at = clock.unix_timestamp + 3_600;
+    warp_to(&mut context, 100, ends_at + 1).await;
+    
+    // Only the owner's seed can start it while the reveal window is open
+    assert_survey_error(
+        send_unsigned(&mut context, start_lottery_draw_ix(&survey, None)).await,
+        SurveyError::RevealWindowOpen,
+    );
+    assert_survey_error(
+        send_unsigned(&mut context, start_lottery_draw_ix(&survey, Some([8; 32]))).await,
+        SurveyError::InvalidSeedReveal,
+    );
+    assert_survey_error(
+        send_unsigned(&mut context, draw_winners_ix(&survey)).await,
+        SurveyError::DrawNotStarted,
+    );
+    
+    send_unsigned(&mut context, start_lottery_draw_ix(&survey, Some(seed))).await.unwrap();
+    let survey_data = get_survey(&mut context.banks_client, survey.survey_account).await;
+    assert_eq!(survey_data.lottery_draw_slot, 100 + LOTTERY_DRAW_DELAY_SLOTS);
+    assert_survey_error(
+        send_unsigned(&mut context, start_lottery_draw_ix(&survey, Some(seed))).await,
+        SurveyError::DrawAlreadyStarted,
+    );
+    
+    // The draw slot's hash does not exist yet
+    assert_survey_error(
+        send_unsigned(&mut context, draw_winners_ix(&survey)).await,
+        SurveyError::DrawSlotNotReached,
+    );
+    
+    // Warping skips slots, so the first slot produced after the draw slot counts
+    warp_to(&mut context, 200, ends_at + 1).await;
+    send_unsigned(&mut context, draw_winners_ix(&survey)).await.unwrap();
+    
+    let survey_data = get_survey(&mut context.banks_client, survey.survey_account).await;
+    assert!(bool::from(survey_data.winners_drawn));
+    assert_eq!(survey_data.lottery_entries, 5);
+    assert_eq!(
+        survey_data.lottery_seed,
+        hashv(&[&seed, &draw_slot_hash(&mut context, 100 + LOTTERY_DRAW_DELAY_SLOTS).await]).to_bytes()
+    );
+    
+    // Exactly `max_participants` entries win; the rest cannot claim
+    let mut winners = 0;
+    for participant in &participants {
+        match claim_reward(&mut context.banks_client, &payer, &survey, participant).await {
+            Ok(()) => winners += 1,
+            result => assert_survey_error(result, SurveyError::NotAWinner),
+        }
+    }
+    assert_eq!(winners, 2);
+    assert_survey_error(
+        claim_reward(&mut context.banks_client, &payer, &survey, &outsider).await,
+        SurveyError::NotAWinner,
+    );
+    
+    let survey_data = get_survey(&mut context.banks_client, survey.survey_account).await;
+    assert_eq!(survey_data.current_participants, 2);
+    assert!(survey_data.is_settled());
+}
+
+#[tokio::test]
+async fn test_lottery_draw_falls_back_without_reveal() {
+    let (mut program_test, mut survey) = program_test("test_survey_lottery_fallback");
+    let commitment = seed_commitment(&[9; 32]);
+    survey.lottery_commitment = Some(commitment);
+    let participants: Vec<Keypair> = (0..3).map(|_| add_participant(&mut program_test)).collect();
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let ends_at = clock.unix_timestamp + 3_600;
+    let sol_reward_amount = 1_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_with_schedule_ix(
+                &survey,
+                sol_reward_amount,
+                0,
+                1,
+                (clock.unix_timestamp - 100, ends_at, ends_at + 2 * LOTTERY_REVEAL_WINDOW),
+            ),
+            fund_survey_ix(&survey, sol_reward_amount, 0),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    
+    for participant in &participants {
+        enter_lottery(&mut context.banks_client, &payer, &survey, participant, 0).await.unwrap();
+    }
+    
+    // Once the owner lets the reveal window pass, anyone can start the draw
+    warp_to(&mut context, 100, ends_at + LOTTERY_REVEAL_WINDOW + 1).await;
+    send_unsigned(&mut context, start_lottery_draw_ix(&survey, None)).await.unwrap();
+    
+    // The winner count is settled from then on
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(
+            &survey,
+            SurveyUpdate {
+                max_participants: Some(3),
+                ..Default::default()
+            },
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    assert_survey_error(
+        context.banks_client.process_transaction(transaction).await,
+        SurveyError::InvalidUpdate,
+    );
+    
+    warp_to(&mut context, 200, ends_at + LOTTERY_REVEAL_WINDOW + 1).await;
+    send_unsigned(&mut context, draw_winners_ix(&survey)).await.unwrap();
+    
+    // The commitment stands in for the withheld seed
+    let survey_data = get_survey(&mut context.banks_client, survey.survey_account).await;
+    assert_eq!(
+        survey_data.lottery_seed,
+        hashv(&[&commitment, &draw_slot_hash(&mut context, survey_data.lottery_draw_slot).await])
+            .to_bytes()
+    );
+    
+    let mut winners = 0;
+    for participant in &participants {
+        match claim_reward(&mut context.banks_client, &payer, &survey, participant).await {
+            Ok(()) => winners += 1,
+            result => assert_survey_error(result, SurveyError::NotAWinner),
+        }
+    }
+    assert_eq!(winners, 1);
+}
+
+#[tokio::test]
+async fn test_lottery_draw_slot_is_final() {
+    let (mut program_test, mut survey) = program_test("test_survey_lottery_final");
+    let seed = [7; 32];
+    survey.lottery_commitment = Some(seed_commitment(&seed));
+    let participants: Vec<Keypair> = (0..3).map(|_| add_participant(&mut program_test)).collect();
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let ends_at = clock.unix_timestamp + 3_600;
+    let sol_reward_amount = 1_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_with_schedule_ix(
+                &survey,
+                sol_reward_amount,
+                0,
+                1,
+                (clock.unix_timestamp - 100, ends_at, ends_at + 3_600),
+            ),
+            fund_survey_ix(&survey, sol_reward_amount, 0),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    
+    for participant in &participants {
+        enter_lottery(&mut context.banks_client, &payer, &survey, participant, 0).await.unwrap();
+    }
+    
+    warp_to(&mut context, 100, ends_at + 1).await;
+    send_unsigned(&mut context, start_lottery_draw_ix(&survey, Some(seed))).await.unwrap();
+    
+    // Nobody completes the draw before its slot ages out of SlotHashes
+    warp_to(&mut context, 1_000, ends_at + 1).await;
+    let recent_slots: Vec<_> = (489..1_000).map(|slot| (slot, Hash::new_unique())).collect();
+    context.set_sysvar(&SlotHashes::new(&recent_slots));
+    send_unsigned(&mut context, draw_winners_ix(&survey)).await.unwrap();
+    
+    // The draw slot stays pinned and the draw completes from the seed alone
+    let survey_data = get_survey(&mut context.banks_client, survey.survey_account).await;
+    assert!(bool::from(survey_data.winners_drawn));
+    assert_eq!(survey_data.lottery_draw_slot, 100 + LOTTERY_DRAW_DELAY_SLOTS);
+    assert_eq!(survey_data.lottery_seed, seed);
+    assert_survey_error(
+        send_unsigned(&mut context, draw_winners_ix(&survey)).await,
+        SurveyError::WinnersAlreadyDrawn,
+    );
+    
+    let mut winners = 0;
+    for participant in &participants {
+        match claim_reward(&mut context.banks_client, &payer, &survey, participant).await {
+            Ok(()) => winners += 1,
+            result => assert_survey_error(result, SurveyError::NotAWinner),
+        }
+    }
+    assert_eq!(winners, 1);
+}
+
+#[tokio::test]
+async fn test_referral_bonus() {
+    let (mut program_test, survey) = program_test("test_survey_referrals");
+    let participants: Vec<Keypair> = (0..4).map(|_| add_participant(&mut program_test)).collect();
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let (sol_reward_amount, token_reward_amount) = (1_000_000, 100);
+    let (sol_bonus, token_bonus) = (500_000, 50);
+    let referral_bonus = |max_per_referrer| SurveyUpdate {
+        referral_bonus: Some(ReferralBonusConfig {
+            sol_amount: sol_bonus,
+            token_amount: token_bonus,
+            max_per_referrer,
+        }),
+        ..Default::default()
+    };
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, sol_reward_amount, token_reward_amount, 4),
+            fund_survey_ix(&survey, 4 * sol_reward_amount, 4 * token_reward_amount),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // Every slot may still bring its referrer a bonus, which must be funded
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(&survey, referral_bonus(1))],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    assert_survey_error(
+        banks_client.process_transaction(transaction).await,
+        SurveyError::UnderfundedUpdate,
+    );
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            fund_survey_ix(&survey, 4 * sol_bonus, 4 * token_bonus),
+            update_survey_ix(&survey, referral_bonus(1)),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let [referrer, referred, other, outsider] = &participants[..] else { unreachable!() };
+    claim_reward(&mut banks_client, &payer, &survey, referrer).await.unwrap();
+    claim_referred_reward(&mut banks_client, &payer, &survey, referred, referrer.pubkey())
+        .await
+        .unwrap();
+    assert_survey_error(
+        claim_referred_reward(&mut banks_client, &payer, &survey, other, other.pubkey()).await,
+        SurveyError::SelfReferral,
+    );
+    
+    // Only participants that have claimed can refer others
+    assert_survey_error(
+        claim_referred_reward(&mut banks_client, &payer, &survey, other, outsider.pubkey()).await,
+        SurveyError::ReferrerNotParticipant,
+    );
+    
+    // Past the cap of one referral per referrer the claim still pays, but the
+    // referral is not recorded
+    claim_referred_reward(&mut banks_client, &payer, &survey, other, referrer.pubkey())
+        .await
+        .unwrap();
+    let other_data = get_participant(
+        &mut banks_client,
+        find_participant_address(&survey.program_id, &survey.survey_account, &other.pubkey()).0,
+    )
+    .await;
+    assert!(bool::from(other_data.has_claimed_sol) && bool::from(other_data.has_claimed_token));
+    assert_eq!(
+        get_token_balance(
+            &mut banks_client,
+            get_associated_token_address(&other.pubkey(), &survey.token_mint),
+        )
+        .await,
+        token_reward_amount
+    );
+    assert_eq!(Option::<Pubkey>::from(other_data.referrer), None);
+    assert_eq!(get_survey(&mut banks_client, survey.survey_account).await.referrals, 1);
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(&survey, referral_bonus(2))],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    claim_referred_reward(&mut banks_client, &payer, &survey, outsider, referrer.pubkey())
+        .await
+        .unwrap();
+    
+    let survey_data = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(survey_data.referrals, 2);
+    
+    // Recorded referrals are owed, so their bonus cannot be cut
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(
+            &survey,
+            SurveyUpdate {
+                referral_bonus: Some(ReferralBonusConfig {
+                    sol_amount: 0,
+                    token_amount: 0,
+                    max_per_referrer: 0,
+                }),
+                ..Default::default()
+            },
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    assert_survey_error(
+        banks_client.process_transaction(transaction).await,
+        SurveyError::InvalidUpdate,
+    );
+    
+    // Only the named referrer collects the bonus, once per referral
+    assert_survey_error(
+        claim_referral_bonus(&mut banks_client, &payer, &survey, outsider, &referred.pubkey()).await,
+        SurveyError::InvalidReferrer,
+    );
+    assert_survey_error(
+        claim_referral_bonus(&mut banks_client, &payer, &survey, referrer, &other.pubkey()).await,
+        SurveyError::InvalidReferrer,
+    );
+    
+    let referrer_token_account =
+        get_associated_token_address(&referrer.pubkey(), &survey.token_mint);
+    let balance_before = banks_client.get_balance(referrer.pubkey()).await.unwrap();
+    claim_referral_bonus(&mut banks_client, &payer, &survey, referrer, &referred.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        banks_client.get_balance(referrer.pubkey()).await.unwrap(),
+        balance_before + sol_bonus
+    );
+    assert_eq!(
+        get_token_balance(&mut banks_client, referrer_token_account).await,
+        token_reward_amount + token_bonus
+    );
+    
+    // The referrer pays the fee so the repeated claim is a distinct transaction
+    assert_survey_error(
+        claim_referral_bonus(&mut banks_client, referrer, &survey, referrer, &referred.pubkey()).await,
+        SurveyError::AlreadyClaimed,
+    );
+    
+    // Disabling referrals still pays those already recorded
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(&survey, referral_bonus(0))],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert!(!get_survey(&mut banks_client, survey.survey_account).await.is_settled());
+    claim_referral_bonus(&mut banks_client, referrer, &survey, referrer, &outsider.pubkey())
+        .await
+        .unwrap();
+    
+    let survey_data = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(survey_data.referral_bonuses_paid, 2);
+    assert!(survey_data.is_settled());
+    let referrer_data = get_participant(
+        &mut banks_client,
+        find_participant_address(&survey.program_id, &survey.survey_account, &referrer.pubkey()).0,
+    )
+    .await;
+    assert_eq!(u32::from(referrer_data.referrals), 2);
+    assert_eq!(u32::from(referrer_data.referral_bonuses), 2);
+}
+
+#[tokio::test]
+async fn test_token_2022_reward_mint_with_transfer_fee() {
+    let (mut program_test, survey) = program_test_with_mint(
+        "test_survey_fee",
+        spl_token_2022::id(),
+        &[ExtensionType::TransferFeeConfig],
+    );
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let token_reward_amount = 100_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, token_reward_amount, 10),
+            fund_survey_ix(&survey, 10_000_000, 2_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // Deposits are recorded net of the 1% fee
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.token_program, spl_token_2022::id());
+    assert_eq!(state.total_tokens_deposited, 1_980_000_000);
+    assert_eq!(get_token_balance(&mut banks_client, survey.token_pool).await, 1_980_000_000);
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    // The participant receives the advertised amount
+    let participant_token_account = get_associated_token_address_with_program_id(
+        &participant.pubkey(),
+        &survey.token_mint,
+        &spl_token_2022::id(),
+    );
+    assert_eq!(
+        get_token_balance(&mut banks_client, participant_token_account).await,
+        token_reward_amount
+    );
+    
+    // Closing harvests the fees withheld in the pool so it can be closed
+    let mut transaction =
+        Transaction::new_with_payer(&[close_survey_ix(&survey, true)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    assert!(banks_client.get_account(survey.token_pool).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_token_2022_transfer_fee_counts_toward_funding() {
+    let (mut program_test, survey) = program_test_with_mint(
+        "test_survey_fee_funding",
+        spl_token_2022::id(),
+        &[ExtensionType::TransferFeeConfig],
+    );
+    let participants: Vec<Keypair> = (0..3).map(|_| add_participant(&mut program_test)).collect();
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let token_reward_amount = 100_000_000;
+    
+    let mint = banks_client.get_account(survey.token_mint).await.unwrap().unwrap();
+    let mint = StateWithExtensionsOwned::<Mint>::unpack(mint.data).unwrap();
+    let fee_config = *mint.get_extension::<TransferFeeConfig>().unwrap();
+    let gross = |amount: u64| {
+        fee_config.calculate_inverse_epoch_fee(0, amount).map(|fee| amount + fee)
+    };
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_survey_ix(&survey, 0, token_reward_amount, 3)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    let required = state.outstanding_tokens(gross).unwrap();
+    assert_eq!(required, 3 * gross(token_reward_amount).unwrap());
+    
+    let fund = |token_amount: u64| {
+        let mut transaction = Transaction::new_with_payer(
+            &[fund_survey_ix(&survey, 0, gross(token_amount).unwrap())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+        transaction
+    };
+    
+    // The net rewards alone leave nothing for the fees
+    banks_client.process_transaction(fund(3 * token_reward_amount)).await.unwrap();
+    let result = claim_reward(&mut banks_client, &payer, &survey, &participants[0]).await;
+    assert_survey_error(result, SurveyError::SurveyNotFunded);
+    
+    // Topping up to exactly the requirement pays every slot in full
+    banks_client
+        .process_transaction(fund(required - 3 * token_reward_amount))
+        .await
+        .unwrap();
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.total_tokens_deposited, required);
+    
+    for participant in &participants {
+        claim_reward(&mut banks_client, &payer, &survey, participant).await.unwrap();
+        let participant_token_account = get_associated_token_address_with_program_id(
+            &participant.pubkey(),
+            &survey.token_mint,
+            &spl_token_2022::id(),
+        );
+        assert_eq!(
+            get_token_balance(&mut banks_client, participant_token_account).await,
+            token_reward_amount
+        );
+    }
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert!(state.is_settled());
+    assert_eq!(state.total_tokens_paid, required);
+    assert_eq!(get_token_balance(&mut banks_client, survey.token_pool).await, 0);
+}
+
+#[tokio::test]
+async fn test_initialize_survey_rejects_unsupported_mint_extension() {
+    let (program_test, survey) = program_test_with_mint(
+        "test_survey_delegate",
+        spl_token_2022::id(),
+        &[ExtensionType::PermanentDelegate],
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::UnsupportedMintExtension);
+}
+
+#[tokio::test]
+async fn test_claim_reward_rejects_when_full() {
+    let (mut program_test, survey) = program_test("test_survey_full");
+    let first = add_participant(&mut program_test);
+    let second = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 1),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &first).await.unwrap();
+    
+    let result = claim_reward(&mut banks_client, &payer, &survey, &second).await;
+    assert_survey_error(result, SurveyError::SurveyFull);
+}
+
+#[tokio::test]
+async fn test_claim_reward_requires_full_funding() {
+    let (mut program_test, survey) = program_test("test_survey_unfunded");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    // Nothing deposited yet
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_survey_ix(&survey, 1_000_000, 0, 10)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let result = claim_reward(&mut banks_client, &payer, &survey, &participant).await;
+    assert_survey_error(result, SurveyError::SurveyNotFunded);
+    
+    // Enough for the first claim but not for all 10 slots
+    let mut transaction = Transaction::new_with_payer(
+        &[fund_survey_ix(&survey, 9_999_999, 0)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let result = claim_reward(&mut banks_client, &payer, &survey, &participant).await;
+    assert_survey_error(result, SurveyError::SurveyNotFunded);
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[fund_survey_ix(&survey, 1, 0)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.total_sol_paid, 1_000_000);
+    assert!(state.is_funded(Some));
+}
+
+#[tokio::test]
+async fn test_claim_reward_rejects_foreign_token_account() {
+    let (mut program_test, survey) = program_test("test_survey_foreign_ata");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // Point the reward at the owner's token account instead of the participant's ATA
+    let mut claim = claim_reward_ix(
+        &survey,
+        &participant.pubkey(),
+        AccountMeta::new_readonly(survey.verifier.pubkey(), true),
+        0,
+    );
+    claim.accounts[3].pubkey = survey.owner_token_account;
+    
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[claim], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &participant, &survey.verifier], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::InvalidParticipantTokenAccount);
+}
+
+#[tokio::test]
+async fn test_claim_reward_requires_verifier() {
+    let (mut program_test, survey) = program_test("test_survey_verifier");
+    let participant = add_participant(&mut program_test);
+    let impostor = Keypair::new();
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let now = banks_client
+        .get_sysvar::<solana_sdk::clock::Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+    
+    // Signed by someone other than the verifier
+    let result = claim_reward_with_attestation(
+        &mut banks_client,
+        &payer,
+        &survey,
+        &participant,
+        &impostor,
+        now + 600,
+    )
+    .await;
+    assert_survey_error(result, SurveyError::InvalidAttestation);
+    
+    // Signed by the verifier but already expired
+    let result = claim_reward_with_attestation(
+        &mut banks_client,
+        &payer,
+        &survey,
+        &participant,
+        &survey.verifier,
+        now - 1,
+    )
+    .await;
+    assert_survey_error(result, SurveyError::AttestationExpired);
+    
+    claim_reward_with_attestation(
+        &mut banks_client,
+        &payer,
+        &survey,
+        &participant,
+        &survey.verifier,
+        now + 600,
+    )
+    .await
+    .unwrap();
+    
+    let (participant_account, _) = find_participant_address(
+        &survey.program_id,
+        &survey.survey_account,
+        &participant.pubkey(),
+    );
+    let state = get_participant(&mut banks_client, participant_account).await;
+    assert_eq!(state.response_hash, RESPONSE_HASH);
+}
+
+#[tokio::test]
+async fn test_claim_reward_before_start() {
+    let (mut program_test, survey) = program_test("test_survey_window");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let now = banks_client
+        .get_sysvar::<solana_sdk::clock::Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_survey_with_schedule_ix(
+            &survey,
+            0,
+            100_000_000,
+            10,
+            (now + 3_600, now + 7_200, now + 10_800),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let result = claim_reward(&mut banks_client, &payer, &survey, &participant).await;
+    assert_survey_error(result, SurveyError::SurveyNotStarted);
+}
+
+#[tokio::test]
+async fn test_claim_reward_after_deadline() {
+    let (mut program_test, survey) = program_test("test_survey_expired");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let now = banks_client
+        .get_sysvar::<solana_sdk::clock::Clock>()
+        .await
+        .unwrap()
+        .unix_timestamp;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_survey_with_schedule_ix(
+            &survey,
+            0,
+            100_000_000,
+            10,
+            (now - 7_200, now - 3_600, now - 1),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let result = claim_reward(&mut banks_client, &payer, &survey, &participant).await;
+    assert_survey_error(result, SurveyError::SurveyExpired);
+}
+
+#[tokio::test]
+async fn test_claim_reward_after_end() {
+    let (mut program_test, survey) = program_test("test_survey_ended");
+    let early = add_participant(&mut program_test);
+    let late = add_participant(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let ends_at = clock.unix_timestamp + 3_600;
+    let sol_reward_amount = 1_000_000;
+    let token_reward_amount = 100_000_000;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_with_schedule_ix(
+                &survey,
+                sol_reward_amount,
+                token_reward_amount,
+                2,
+                (clock.unix_timestamp - 100, ends_at, ends_at + 3_600),
+            ),
+            fund_survey_ix(&survey, 2 * sol_reward_amount, 2 * token_reward_amount),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_selected_reward(&mut context.banks_client, &payer, &survey, &early, RewardSelector::Sol, 0)
+        .await
+        .unwrap();
+    
+    // Between `ends_at` and the claim deadline no one new can claim, but a
+    // participant who already claimed can collect their remaining leg
+    warp_to(&mut context, 100, ends_at + 1).await;
+    assert_survey_error(
+        claim_reward(&mut context.banks_client, &payer, &survey, &late).await,
+        SurveyError::SurveyEnded,
+    );
+    claim_selected_reward(&mut context.banks_client, &payer, &survey, &early, RewardSelector::Token, 0)
+        .await
+        .unwrap();
+    
+    let state = get_survey(&mut context.banks_client, survey.survey_account).await;
+    assert_eq!(state.current_participants, 1);
+    assert_eq!(state.total_tokens_paid, token_reward_amount);
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_survey() {
+    let (mut program_test, survey) = program_test("test_survey_pause");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+            set_paused_ix(&survey, &survey.owner.pubkey(), true),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let result = claim_reward(&mut banks_client, &payer, &survey, &participant).await;
+    assert_survey_error(result, SurveyError::SurveyPaused);
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[set_paused_ix(&survey, &survey.owner.pubkey(), false)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert!(!bool::from(state.is_paused));
+    assert_eq!(state.current_participants, 1);
+    assert_eq!(state.total_sol_deposited, 10_000_000);
+}
+
+#[tokio::test]
+async fn test_rejects_spoofed_accounts() {
+    let (mut program_test, survey) = program_test("test_survey_spoofed");
+    let participant = add_participant(&mut program_test);
+    
+    // A look-alike survey account owned by some other program
+    let forged_survey = Pubkey::new_unique();
+    program_test.add_account(
+        forged_survey,
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![0; SurveyAccount::LEN],
+            owner: Pubkey::new_unique(),
+            ..Default::default()
+        },
+    );
+    
+    // A survey account still in the version 1 (Borsh) layout
+    let legacy_survey = Pubkey::new_unique();
+    let mut legacy_data = vec![0; SurveyAccount::LEN];
+    legacy_data[..DISCRIMINATOR_LEN].copy_from_slice(&SurveyAccount::DISCRIMINATOR);
+    legacy_data[DISCRIMINATOR_LEN] = 1;
+    program_test.add_account(
+        legacy_survey,
+        Account {
+            lamports: 1_000_000_000,
+            data: legacy_data,
+            owner: survey.program_id,
+            ..Default::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let send = |instruction: Instruction| {
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+        transaction
+    };
+    
+    let mut pause = set_paused_ix(&survey, &survey.owner.pubkey(), true);
+    pause.accounts[1].pubkey = forged_survey;
+    let result = banks_client.process_transaction(send(pause)).await;
+    assert_survey_error(result, SurveyError::InvalidAccountOwner);
+    
+    let mut pause = set_paused_ix(&survey, &survey.owner.pubkey(), true);
+    pause.accounts[1].pubkey = legacy_survey;
+    let result = banks_client.process_transaction(send(pause)).await;
+    assert_survey_error(result, SurveyError::UnsupportedAccountVersion);
+    
+    let mut pause = set_paused_ix(&survey, &survey.owner.pubkey(), true);
+    pause.accounts[1].is_writable = false;
+    let result = banks_client.process_transaction(send(pause)).await;
+    assert_survey_error(result, SurveyError::AccountNotWritable);
+    
+    // A claim that swaps in an impostor for the system program
+    let mut claim = claim_reward_ix(
+        &survey,
+        &participant.pubkey(),
+        AccountMeta::new_readonly(survey.verifier.pubkey(), true),
+        0,
+    );
+    claim.accounts[6].pubkey = Pubkey::new_unique();
+    let mut transaction = Transaction::new_with_payer(&[claim], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &participant, &survey.verifier], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::InvalidProgramId);
+    
+    // A participant record is program-owned but must not pass for a survey
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    let (participant_account, _) = find_participant_address(
+        &survey.program_id,
+        &survey.survey_account,
+        &participant.pubkey(),
+    );
+    let mut pause = set_paused_ix(&survey, &survey.owner.pubkey(), true);
+    pause.accounts[1].pubkey = participant_account;
+    let result = banks_client.process_transaction(send(pause)).await;
+    assert_survey_error(result, SurveyError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn test_update_survey() {
+    let (program_test, survey) = program_test("test_survey_update");
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    // Exactly enough escrow for 10 participants
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let send = |update: SurveyUpdate| {
+        let mut transaction = Transaction::new_with_payer(
+            &[update_survey_ix(&survey, update)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+        transaction
+    };
+    
+    // Capacity can't shrink
+    let result = banks_client
+        .process_transaction(send(SurveyUpdate {
+            max_participants: Some(5),
+            ..Default::default()
+        }))
+        .await;
+    assert_survey_error(result, SurveyError::InvalidUpdate);
+    
+    // Doubling the SOL reward would leave the funded campaign short
+    let result = banks_client
+        .process_transaction(send(SurveyUpdate {
+            sol_reward_amount: Some(2_000_000),
+            ..Default::default()
+        }))
+        .await;
+    assert_survey_error(result, SurveyError::UnderfundedUpdate);
+    
+    let collection = Pubkey::new_unique();
+    banks_client
+        .process_transaction(send(SurveyUpdate {
+            max_participants: Some(20),
+            sol_reward_amount: Some(500_000),
+            token_reward_amount: Some(50_000_000),
+            nft_collection: Some(collection),
+            ..Default::default()
+        }))
+        .await
+        .unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.max_participants, 20);
+    assert_eq!(state.sol_reward_amount, 500_000);
+    assert_eq!(state.token_reward_amount, 50_000_000);
+    assert_eq!(Option::<Pubkey>::from(state.nft_collection), Some(collection));
+}
+
+#[tokio::test]
+async fn test_transfer_ownership_and_operators() {
+    let (program_test, survey) = program_test("test_survey_owner");
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let new_owner = Keypair::new();
+    let operator = Keypair::new();
+    let survey_id = survey.survey_id.clone();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            survey_admin_ix(
+                &survey,
+                &survey.owner.pubkey(),
+                SurveyInstruction::ProposeOwner {
+                    survey_id: survey_id.clone(),
+                    new_owner: new_owner.pubkey(),
+                },
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // Proposing does not hand over control yet
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.owner, survey.owner.pubkey());
+    assert_eq!(Option::<Pubkey>::from(state.pending_owner), Some(new_owner.pubkey()));
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            survey_admin_ix(
+                &survey,
+                &new_owner.pubkey(),
+                SurveyInstruction::AcceptOwner {
+                    survey_id: survey_id.clone(),
+                },
+            ),
+            survey_admin_ix(
+                &survey,
+                &new_owner.pubkey(),
+                SurveyInstruction::SetOperators {
+                    survey_id: survey_id.clone(),
+                    operators: vec![operator.pubkey()],
+                },
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &new_owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(state.owner, new_owner.pubkey());
+    assert_eq!(Option::<Pubkey>::from(state.pending_owner), None);
+    assert_eq!(state.operators(), [operator.pubkey()]);
+    
+    // The previous owner has lost control
+    let mut transaction = Transaction::new_with_payer(
+        &[set_paused_ix(&survey, &survey.owner.pubkey(), true)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::Unauthorized);
+    
+    // Operators can pause but not manage the survey
+    let mut transaction = Transaction::new_with_payer(
+        &[set_paused_ix(&survey, &operator.pubkey(), true)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &operator], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[survey_admin_ix(
+            &survey,
+            &operator.pubkey(),
+            SurveyInstruction::SetOperators {
+                survey_id,
+                operators: vec![],
+            },
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &operator], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::InvalidOwner);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/mpl_token_metadata.so, run tests/fixtures/fetch.sh"]
+async fn test_distribute_nft() {
+    let (mut program_test, survey) = program_test("test_survey_nft");
+    add_token_metadata_program(&mut program_test);
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+            update_survey_ix(
+                &survey,
+                SurveyUpdate {
+                    nft_uri: Some("https://example.com/survey.json".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[distribute_nft_ix(&survey, &survey.owner.pubkey(), &participant.pubkey(), None)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let (nft_mint, _) =
+        find_nft_mint_address(&survey.program_id, &survey.survey_account, &participant.pubkey());
+    assert_eq!(
+        get_token_balance(
+            &mut banks_client,
+            get_associated_token_address(&participant.pubkey(), &nft_mint)
+        )
+        .await,
+        1
+    );
+    
+    // The master edition now holds the mint authority, so no more can be minted
+    let mint = banks_client.get_account(nft_mint).await.unwrap().unwrap();
+    let mint = Mint::unpack(&mint.data).unwrap();
+    assert_eq!(mint.supply, 1);
+    assert_eq!(mint.mint_authority, Some(MasterEdition::find_pda(&nft_mint).0).into());
+    
+    let (metadata, _) = Metadata::find_pda(&nft_mint);
+    let metadata = banks_client.get_account(metadata).await.unwrap().unwrap();
+    let metadata = Metadata::from_bytes(&metadata.data).unwrap();
+    assert_eq!(metadata.name.trim_end_matches('\0'), survey.survey_id);
+    assert_eq!(metadata.update_authority, survey.vault_authority);
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[distribute_nft_ix(&survey, &survey.owner.pubkey(), &participant.pubkey(), None)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::AlreadyClaimed);
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/mpl_token_metadata.so, run tests/fixtures/fetch.sh"]
+async fn test_create_collection() {
+    let (mut program_test, survey) = program_test("test_survey_collection");
+    add_token_metadata_program(&mut program_test);
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+            create_collection_ix(&survey),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let (collection_mint, _) =
+        find_collection_mint_address(&survey.program_id, &survey.survey_account);
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(Option::<Pubkey>::from(state.nft_collection), Some(collection_mint));
+    assert_eq!(
+        get_token_balance(
+            &mut banks_client,
+            get_associated_token_address(&survey.vault_authority, &collection_mint)
+        )
+        .await,
+        1
+    );
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[distribute_nft_ix(
+            &survey,
+            &survey.owner.pubkey(),
+            &participant.pubkey(),
+            Some(collection_mint),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let (nft_mint, _) =
+        find_nft_mint_address(&survey.program_id, &survey.survey_account, &participant.pubkey());
+    let metadata = banks_client
+        .get_account(Metadata::find_pda(&nft_mint).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let collection = Metadata::from_bytes(&metadata.data).unwrap().collection.unwrap();
+    assert_eq!(collection.key, collection_mint);
+    assert!(collection.verified);
+}
+
+#[tokio::test]
+async fn test_distribute_soulbound_badge() {
+    let (mut program_test, survey) = program_test("test_survey_badge");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+            update_survey_ix(
+                &survey,
+                SurveyUpdate {
+                    soulbound_nfts: Some(true),
+                    nft_uri: Some("https://example.com/badge.json".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[distribute_badge_ix(&survey, &participant.pubkey())],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let (badge_mint, _) =
+        find_nft_mint_address(&survey.program_id, &survey.survey_account, &participant.pubkey());
+    let badge_account = get_associated_token_address_with_program_id(
+        &participant.pubkey(),
+        &badge_mint,
+        &spl_token_2022::id(),
+    );
+    let account = banks_client.get_account(badge_account).await.unwrap().unwrap();
+    let token_account = StateWithExtensionsOwned::<TokenAccount>::unpack(account.data).unwrap();
+    assert_eq!(token_account.base.amount, 1);
+    assert!(token_account.get_extension::<NonTransferableAccount>().is_ok());
+    
+    let mint = banks_client.get_account(badge_mint).await.unwrap().unwrap();
+    let mint = StateWithExtensionsOwned::<Mint>::unpack(mint.data).unwrap();
+    assert_eq!(mint.base.supply, 1);
+    assert!(mint.base.mint_authority.is_none());
+    assert!(mint.get_extension::<NonTransferable>().is_ok());
+    let metadata = mint.get_variable_len_extension::<TokenMetadata>().unwrap();
+    assert_eq!(metadata.name, survey.survey_id);
+    assert_eq!(metadata.uri, "https://example.com/badge.json");
+    
+    // Badges cannot change hands
+    let recipient = Keypair::new();
+    let recipient_account = get_associated_token_address_with_program_id(
+        &recipient.pubkey(),
+        &badge_mint,
+        &spl_token_2022::id(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_associated_token_account(
+                &payer.pubkey(),
+                &recipient.pubkey(),
+                &badge_mint,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::transfer_checked(
+                &spl_token_2022::id(),
+                &badge_account,
+                &badge_mint,
+                &recipient_account,
+                &participant.pubkey(),
+                &[],
+                1,
+                0,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &participant], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(TokenError::NonTransferable as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_create_collection_rejects_existing_collection() {
+    let (program_test, survey) = program_test("test_survey_collection_set");
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            update_survey_ix(
+                &survey,
+                SurveyUpdate {
+                    nft_collection: Some(Pubkey::new_unique()),
+                    ..Default::default()
+                },
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    let mut transaction =
+        Transaction::new_with_payer(&[create_collection_ix(&survey)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::CollectionAlreadySet);
+    
+    // Nor can an update swap it for another
+    let mut transaction = Transaction::new_with_payer(
+        &[update_survey_ix(
+            &survey,
+            SurveyUpdate {
+                nft_collection: Some(Pubkey::new_unique()),
+                ..Default::default()
+            },
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::CollectionAlreadySet);
+}
+
+#[tokio::test]
+async fn test_distribute_compressed_nft() {
+    let (mut program_test, survey) = program_test("test_survey_cnft");
+    program_test.add_program(
+        "spl_account_compression",
+        ACCOUNT_COMPRESSION_PROGRAM_ID,
+        processor!(process_account_compression),
+    );
+    program_test.add_program(
+        "spl_noop",
+        NOOP_PROGRAM_ID,
+        processor!(spl_noop::noop),
+    );
+    let first = add_participant(&mut program_test);
+    let second = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let merkle_tree = Keypair::new();
+    let tree_size = merkle_tree_size(14, 64);
+    let rent = banks_client.get_rent().await.unwrap();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+            solana_sdk::system_instruction::create_account(
+                &payer.pubkey(),
+                &merkle_tree.pubkey(),
+                rent.minimum_balance(tree_size),
+                tree_size as u64,
+                &ACCOUNT_COMPRESSION_PROGRAM_ID,
+            ),
+            create_nft_tree_ix(&survey, &merkle_tree.pubkey()),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner, &merkle_tree], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    for (index, participant) in [&first, &second].into_iter().enumerate() {
+        claim_reward(&mut banks_client, &payer, &survey, participant).await.unwrap();
+        
+        let mut transaction = Transaction::new_with_payer(
+            &[distribute_compressed_nft_ix(&survey, &merkle_tree.pubkey(), &participant.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        
+        let (participant_account, _) = find_participant_address(
+            &survey.program_id,
+            &survey.survey_account,
+            &participant.pubkey(),
+        );
+        let state = get_participant(&mut banks_client, participant_account).await;
+        assert!(bool::from(state.has_received_nft));
+        assert_eq!(state.nft_leaf_index(), Some(index as u64));
+    }
+    
+    let state = get_survey(&mut banks_client, survey.survey_account).await;
+    assert_eq!(Option::<Pubkey>::from(state.nft_tree), Some(merkle_tree.pubkey()));
+    assert_eq!(state.nft_tree_leaf_count, 2);
+}
+
+#[tokio::test]
+async fn test_distribute_compressed_nft_requires_tree() {
+    let (mut program_test, survey) = program_test("test_survey_cnft_tree");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[distribute_compressed_nft_ix(&survey, &Pubkey::new_unique(), &participant.pubkey())],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::MissingNftTree);
+}
+
+#[tokio::test]
+async fn test_distribute_nft_requires_owner_or_operator() {
+    let (mut program_test, survey) = program_test("test_survey_nft_auth");
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[distribute_nft_ix(&survey, &participant.pubkey(), &participant.pubkey(), None)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &participant], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_close_survey() {
+    let (program_test, survey) = program_test("test_survey_close");
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    // Slots remain and the claim window is open
+    let mut transaction = Transaction::new_with_payer(
+        &[close_survey_ix(&survey, false)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::UnclaimedRewardsOutstanding);
+    
+    // Without withheld fees to harvest the mint can stay read-only
+    let mut close = close_survey_ix(&survey, true);
+    close.accounts[7].is_writable = false;
+    let mut transaction = Transaction::new_with_payer(&[close], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    
+    assert_eq!(
+        get_token_balance(&mut banks_client, survey.owner_token_account).await,
+        OWNER_TOKEN_BALANCE
+    );
+    assert!(banks_client.get_account(survey.token_pool).await.unwrap().is_none());
+    
+    // Only a rent-exempt tombstone is left, which keeps the ID from being reused
+    let tombstone = banks_client.get_account(survey.survey_account).await.unwrap().unwrap();
+    assert_eq!(tombstone.owner, survey.program_id);
+    assert_eq!(tombstone.data, CLOSED_SURVEY_DISCRIMINATOR);
+    assert_eq!(tombstone.lamports, Rent::default().minimum_balance(DISCRIMINATOR_LEN));
+    
+    let mut transaction = Transaction::new_with_payer(
+        &[initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_survey_error(result, SurveyError::SurveyAlreadyExists);
+}
+
+#[tokio::test]
+async fn test_close_survey_harvests_withheld_fees() {
+    let (mut program_test, survey) = program_test_with_mint(
+        "test_survey_close_fee",
+        spl_token_2022::id(),
+        &[ExtensionType::TransferFeeConfig],
+    );
+    let participant = add_participant(&mut program_test);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    
+    let mint = banks_client.get_account(survey.token_mint).await.unwrap().unwrap();
+    let mint = StateWithExtensionsOwned::<Mint>::unpack(mint.data).unwrap();
+    let fee_config = *mint.get_extension::<TransferFeeConfig>().unwrap();
+    
+    let deposit = 2_000_000_000;
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            initialize_survey_ix(&survey, 1_000_000, 100_000_000, 10),
+            fund_survey_ix(&survey, 10_000_000, deposit),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    claim_reward(&mut banks_client, &payer, &survey, &participant).await.unwrap();
+    
+    let send = |close: Instruction| {
+        let mut transaction = Transaction::new_with_payer(&[close], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &survey.owner], recent_blockhash);
+        transaction
+    };
+    
+    // Slots remain and the claim window is open
+    let result = banks_client.process_transaction(send(close_survey_ix(&survey, false))).await;
+    assert_survey_error(result, SurveyError::UnclaimedRewardsOutstanding);
+    
+    // The deposit fee withheld in the pool has to be harvested to the mint
+    let mut close = close_survey_ix(&survey, true);
+    close.accounts[7].is_writable = false;
+    let result = banks_client.process_transaction(send(close)).await;
+    assert_survey_error(result, SurveyError::AccountNotWritable);
+    
+    let pool_amount = get_token_balance(&mut banks_client, survey.token_pool).await;
+    let owner_balance = get_token_balance(&mut banks_client, survey.owner_token_account).await;
+    banks_client.process_transaction(send(close_survey_ix(&survey, true))).await.unwrap();
+    
+    // The pool is swept to the owner, less the fee on that transfer
+    let sweep_fee = fee_config.calculate_epoch_fee(0, pool_amount).unwrap();
+    assert_eq!(
+        get_token_balance(&mut banks_client, survey.owner_token_account).await,
+        owner_balance + pool_amount - sweep_fee
+    );
+    assert!(banks_client.get_account(survey.token_pool).await.unwrap().is_none());
+    
+    let mint = banks_client.get_account(survey.token_mint).await.unwrap().unwrap();
+    let mint = StateWithExtensionsOwned::<Mint>::unpack(mint.data).unwrap();
+    assert_eq!(
+        u64::from(mint.get_extension::<TransferFeeConfig>().unwrap().withheld_amount),
+        fee_config.calculate_epoch_fee(0, deposit).unwrap()
+    );
+    
+    let tombstone = banks_client.get_account(survey.survey_account).await.unwrap().unwrap();
+    assert_eq!(tombstone.data, CLOSED_SURVEY_DISCRIMINATOR);
+}